@@ -1,14 +1,59 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use reqwest::Client;
 
-#[derive(Deserialize, Debug)]
+use crate::metadata::Attribute;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Intent {
-    pub action: String, // SWAP, TRANSFER, MINT_NFT, LP
+    pub action: String, // SWAP, TRANSFER, TRANSFER_NFT, MINT_NFT, MINT_CNFT, STAKE (+ stake management), LP, LP_REMOVE, WRAP, UNWRAP, CLOSE_EMPTY, BURN, CREATE_TOKEN (+ authority actions), BATCH_TRANSFER, SPLIT_PAYMENT, LIMIT_ORDER (+ list / cancel), AIRDROP, FAUCET, QUERY_BALANCE
     pub amount: f64,
     pub token_in: String,
     pub token_out: String,
     pub recipient: Option<String>,
-    pub nft_name: Option<String>, // For MINT_NFT
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub recipients: Vec<String>, // Every address for BATCH_TRANSFER / SPLIT_PAYMENT
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub weights: Vec<f64>, // SPLIT_PAYMENT shares in recipient order; empty splits equally
+    pub nft_name: Option<String>, // For MINT_NFT / CREATE_TOKEN; name or mint for TRANSFER_NFT
+    #[serde(default)]
+    pub nft_symbol: Option<String>, // Also the CREATE_TOKEN symbol
+    #[serde(default)]
+    pub nft_description: Option<String>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub nft_attributes: Vec<Attribute>,
+    #[serde(default)]
+    pub memo: Option<String>, // On-chain reference for TRANSFER / SWAP
+    #[serde(default)]
+    pub validator: Option<String>, // Vote account, validator name or "best" for STAKE
+    #[serde(default)]
+    pub stake_account: Option<String>, // Target for UNSTAKE / WITHDRAW_STAKE / SPLIT_STAKE, destination for MERGE_STAKE
+    #[serde(default)]
+    pub source_stake_account: Option<String>, // Merged away by MERGE_STAKE
+    #[serde(default)]
+    pub pool: Option<String>, // Explicit pool address for LP / LP_REMOVE
+    #[serde(default)]
+    pub slippage_bps: Option<u16>,
+    #[serde(default)]
+    pub close_account: Option<bool>, // Close the token account after BURN
+    #[serde(default)]
+    pub decimals: Option<u8>, // For CREATE_TOKEN
+    #[serde(default)]
+    pub authority: Option<String>, // "mint" or "freeze" for SET_AUTHORITY / REVOKE_AUTHORITY
+    #[serde(default)]
+    pub limit_price: Option<f64>, // LIMIT_ORDER price per unit of the non-stablecoin token
+    #[serde(default)]
+    pub order: Option<String>, // Order to CANCEL_ORDER; none cancels all
+    #[serde(default)]
+    pub schedule: Option<String>, // Cadence for recurring SWAP / TRANSFER / SPLIT_PAYMENT, e.g. "every monday"
+}
+
+/// The model sometimes sends `null` for a list it has nothing for
+fn null_as_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(Option::<Vec<T>>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 pub async fn parse_intent(api_key: &str, prompt: &str) -> Result<Intent, Box<dyn std::error::Error>> {
@@ -25,18 +70,78 @@ pub async fn parse_intent(api_key: &str, prompt: &str) -> Result<Intent, Box<dyn
     You are a Solana Transaction Parser. Output strictly JSON. No markdown.
     Schema:
     {
-      "action": "SWAP" | "TRANSFER" | "MINT_NFT",
+      "action": "SWAP" | "TRANSFER" | "TRANSFER_NFT" | "MINT_NFT" | "MINT_CNFT" | "STAKE" | "LIST_STAKES" | "UNSTAKE" | "WITHDRAW_STAKE" | "MERGE_STAKE" | "SPLIT_STAKE" | "LP" | "LP_REMOVE" | "WRAP" | "UNWRAP" | "CLOSE_EMPTY" | "BURN" | "CREATE_TOKEN" | "MINT_MORE" | "SET_AUTHORITY" | "REVOKE_AUTHORITY" | "FREEZE" | "THAW" | "BATCH_TRANSFER" | "SPLIT_PAYMENT" | "LIMIT_ORDER" | "LIST_ORDERS" | "CANCEL_ORDER" | "AIRDROP" | "FAUCET" | "QUERY_BALANCE",
       "amount": number (0 if not applicable),
       "token_in": "SOL" | "USDC" | "BONK" (default SOL),
       "token_out": "USDC" (target token),
       "recipient": "PubkeyString" (if transfer),
       "token_out": "USDC" (target token),
       "recipient": "PubkeyString" (if transfer),
-      "nft_name": "String" (if mint)
+      "recipients": ["PubkeyString"] (if BATCH_TRANSFER: every address listed, amount is per recipient; if SPLIT_PAYMENT: every address sharing the amount),
+      "weights": [number] (if SPLIT_PAYMENT with uneven shares, one per recipient in order),
+      "nft_name": "String" (if mint; for TRANSFER_NFT the NFT's name or mint address),
+      "nft_symbol": "String" (if mint, max 10 chars),
+      "nft_description": "String" (if mint),
+      "nft_attributes": [{"trait_type": "String", "value": "String" | number}] (if mint),
+      "memo": "String" (payment reference / note, only if the user gives one),
+      "validator": "String" (if stake: the vote account or validator name as given, or "best" if the user wants a good validator),
+      "stake_account": "String" (stake account to unstake / withdraw from / split, or merge destination; omit if not given),
+      "source_stake_account": "String" (if merge, the account merged away),
+      "pool": "String" (if LP and the user gives a pool address),
+      "slippage_bps": number (only if the user sets slippage; 1% = 100),
+      "close_account": boolean (if burn and the user wants the emptied account closed),
+      "decimals": number (if CREATE_TOKEN and the user gives decimals),
+      "authority": "mint" | "freeze" (if SET_AUTHORITY / REVOKE_AUTHORITY),
+      "limit_price": number (if LIMIT_ORDER: the trigger price in USDC per unit of the other token, e.g. 120 for "SOL at $120"; one side must be USDC or USDT; amount is what the user pays in token_in),
+      "order": "String" (if CANCEL_ORDER and the user names one order; omit to cancel all),
+      "schedule": "String" (only if the user wants it to repeat: "every monday", "daily", "monthly on the 1st", "every 6 hours")
     }
     User: "Swap 1 SOL for USDC" -> {"action":"SWAP", "amount":1, "token_in":"SOL", "token_out":"USDC"}
     User: "Send 0.5 SOL to 8Xy..." -> {"action":"TRANSFER", "amount":0.5, "token_in":"SOL", "token_out":"", "recipient":"8Xy..."}
+    User: "Send 25 USDC to 8Xy... with memo invoice-1042" -> {"action":"TRANSFER", "amount":25, "token_in":"USDC", "token_out":"", "recipient":"8Xy...", "memo":"invoice-1042"}
+    User: "Airdrop me 2 SOL" -> {"action":"AIRDROP", "amount":2, "token_in":"SOL", "token_out":""}
+    User: "I need some devnet SOL" -> {"action":"AIRDROP", "amount":0, "token_in":"SOL", "token_out":""}
+    User: "What's my balance?" -> {"action":"QUERY_BALANCE", "amount":0, "token_in":"", "token_out":""}
+    User: "How much BONK do I have?" -> {"action":"QUERY_BALANCE", "amount":0, "token_in":"BONK", "token_out":""}
+    User: "Give me 500 test USDC" -> {"action":"FAUCET", "amount":500, "token_in":"", "token_out":"USDC"}
+    User: "I need some devnet BONK" -> {"action":"FAUCET", "amount":0, "token_in":"", "token_out":"BONK"}
+    User: "Buy SOL with 500 USDC if it drops to $120" -> {"action":"LIMIT_ORDER", "amount":500, "token_in":"USDC", "token_out":"SOL", "limit_price":120}
+    User: "Buy 1 SOL if it drops to $120" -> {"action":"LIMIT_ORDER", "amount":120, "token_in":"USDC", "token_out":"SOL", "limit_price":120}
+    User: "Sell 2 SOL at $200" -> {"action":"LIMIT_ORDER", "amount":2, "token_in":"SOL", "token_out":"USDC", "limit_price":200}
+    User: "Show my limit orders" -> {"action":"LIST_ORDERS", "amount":0, "token_in":"", "token_out":""}
+    User: "Cancel all my limit orders" -> {"action":"CANCEL_ORDER", "amount":0, "token_in":"", "token_out":""}
+    User: "Buy 0.5 SOL of JUP every Monday" -> {"action":"SWAP", "amount":0.5, "token_in":"SOL", "token_out":"JUP", "schedule":"every monday"}
+    User: "Send 100 USDC to 8Xy... monthly" -> {"action":"TRANSFER", "amount":100, "token_in":"USDC", "token_out":"", "recipient":"8Xy...", "schedule":"monthly"}
+    User: "Send 5 USDC each to 8Xy..., 3Fg... and 9Hk..." -> {"action":"BATCH_TRANSFER", "amount":5, "token_in":"USDC", "token_out":"", "recipients":["8Xy...","3Fg...","9Hk..."]}
+    User: "Pay everyone in this CSV in USDC" -> {"action":"BATCH_TRANSFER", "amount":0, "token_in":"USDC", "token_out":""}
+    User: "Split 30 USDC between 8Xy..., 3Fg... and 9Hk..." -> {"action":"SPLIT_PAYMENT", "amount":30, "token_in":"USDC", "token_out":"", "recipients":["8Xy...","3Fg...","9Hk..."]}
+    User: "Pay 2 SOL 60/40 to 8Xy... and 3Fg..." -> {"action":"SPLIT_PAYMENT", "amount":2, "token_in":"SOL", "token_out":"", "recipients":["8Xy...","3Fg..."], "weights":[60,40]}
+    User: "Send my Mad Lads #123 to 8Xy..." -> {"action":"TRANSFER_NFT", "amount":1, "token_in":"", "token_out":"", "recipient":"8Xy...", "nft_name":"Mad Lads #123"}
+    User: "Stake 10 SOL with validator Vote111..." -> {"action":"STAKE", "amount":10, "token_in":"SOL", "token_out":"", "validator":"Vote111..."}
+    User: "Stake 5 SOL with a good validator" -> {"action":"STAKE", "amount":5, "token_in":"SOL", "token_out":"", "validator":"best"}
+    User: "Stake 3 SOL with Helius" -> {"action":"STAKE", "amount":3, "token_in":"SOL", "token_out":"", "validator":"Helius"}
+    User: "Show my stake accounts" -> {"action":"LIST_STAKES", "amount":0, "token_in":"SOL", "token_out":""}
+    User: "Unstake my SOL" -> {"action":"UNSTAKE", "amount":0, "token_in":"SOL", "token_out":""}
+    User: "Withdraw 2 SOL from stake account 7Ab..." -> {"action":"WITHDRAW_STAKE", "amount":2, "token_in":"SOL", "token_out":"", "stake_account":"7Ab..."}
+    User: "Split 5 SOL off stake account 7Ab..." -> {"action":"SPLIT_STAKE", "amount":5, "token_in":"SOL", "token_out":"", "stake_account":"7Ab..."}
+    User: "Merge stake account 9Cd... into 7Ab..." -> {"action":"MERGE_STAKE", "amount":0, "token_in":"SOL", "token_out":"", "stake_account":"7Ab...", "source_stake_account":"9Cd..."}
+    User: "Add liquidity with 2 SOL to the SOL/USDC pool" -> {"action":"LP", "amount":2, "token_in":"SOL", "token_out":"USDC"}
+    User: "Remove half my SOL-USDC liquidity" -> {"action":"LP_REMOVE", "amount":50, "token_in":"SOL", "token_out":"USDC"}
+    User: "Wrap 1.5 SOL" -> {"action":"WRAP", "amount":1.5, "token_in":"SOL", "token_out":"wSOL"}
+    User: "Unwrap my wSOL" -> {"action":"UNWRAP", "amount":0, "token_in":"wSOL", "token_out":"SOL"}
+    User: "Clean up my wallet" -> {"action":"CLOSE_EMPTY", "amount":0, "token_in":"", "token_out":""}
+    User: "Burn 1M BONK" -> {"action":"BURN", "amount":1000000, "token_in":"BONK", "token_out":""}
+    User: "Burn all my BONK and close the account" -> {"action":"BURN", "amount":0, "token_in":"BONK", "token_out":"", "close_account":true}
+    User: "Burn my Ember NFT" -> {"action":"BURN", "amount":1, "token_in":"", "token_out":"", "nft_name":"Ember"}
+    User: "Create a token called FOO with 6 decimals and 1M supply" -> {"action":"CREATE_TOKEN", "amount":1000000, "token_in":"", "token_out":"", "nft_name":"FOO", "nft_symbol":"FOO", "decimals":6}
+    User: "Mint 500 more of token 4Rt... to 8Xy..." -> {"action":"MINT_MORE", "amount":500, "token_in":"4Rt...", "token_out":"", "recipient":"8Xy..."}
+    User: "Give the mint authority of 4Rt... to 8Xy..." -> {"action":"SET_AUTHORITY", "amount":0, "token_in":"4Rt...", "token_out":"", "recipient":"8Xy...", "authority":"mint"}
+    User: "Revoke the freeze authority on 4Rt..." -> {"action":"REVOKE_AUTHORITY", "amount":0, "token_in":"4Rt...", "token_out":"", "authority":"freeze"}
+    User: "Freeze 8Xy...'s 4Rt... tokens" -> {"action":"FREEZE", "amount":0, "token_in":"4Rt...", "token_out":"", "recipient":"8Xy..."}
+    User: "Thaw 8Xy...'s 4Rt... account" -> {"action":"THAW", "amount":0, "token_in":"4Rt...", "token_out":"", "recipient":"8Xy..."}
     User: "Mint a cool dragon NFT" -> {"action":"MINT_NFT", "amount":1, "token_in":"", "token_out":"", "nft_name":"Cool Dragon"}
+    User: "Mint a compressed NFT called Ticket #1" -> {"action":"MINT_CNFT", "amount":1, "token_in":"", "token_out":"", "nft_name":"Ticket #1"}
+    User: "Mint a red fire dragon NFT called Ember, symbol EMB" -> {"action":"MINT_NFT", "amount":1, "token_in":"", "token_out":"", "nft_name":"Ember", "nft_symbol":"EMB", "nft_description":"A red fire dragon", "nft_attributes":[{"trait_type":"Color","value":"Red"},{"trait_type":"Element","value":"Fire"}]}
     "#;
 
     let request_body = serde_json::json!({
@@ -64,3 +169,19 @@ pub async fn parse_intent(api_key: &str, prompt: &str) -> Result<Intent, Box<dyn
     let intent: Intent = serde_json::from_str(&clean_text)?;
     Ok(intent)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_lists_read_as_empty() {
+        let intent: Intent = serde_json::from_str(r#"{
+            "action": "TRANSFER", "amount": 1, "token_in": "SOL", "token_out": "", "recipient": null,
+            "recipients": null, "weights": null, "nft_name": null, "nft_attributes": null
+        }"#).unwrap();
+        assert!(intent.recipients.is_empty());
+        assert!(intent.weights.is_empty());
+        assert!(intent.nft_attributes.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use std::time::Duration;
+
+use crate::rpc::TEST_NETWORKS;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── TEST SOL AIRDROPS ───────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Used when the user doesn't say how much
+pub const DEFAULT_AIRDROP_SOL: f64 = 1.0;
+
+/// Largest single request the public devnet/testnet faucets accept
+const MAX_AIRDROP_SOL: f64 = 5.0;
+
+/// Give up waiting for the airdrop to confirm after this long
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Faucet error text that means "slow down" rather than "broken"
+const RATE_LIMIT_HINTS: &[&str] = &["429", "too many requests", "rate limit", "airdrop limit", "faucet has run dry"];
+
+pub enum AirdropError {
+    /// The faucet refused because of its rate limit; worth retrying later
+    RateLimited(String),
+    Failed(String),
+}
+
+pub struct Airdrop {
+    pub signature: Signature,
+    pub lamports: u64,
+    /// Wallet balance after the airdrop confirmed
+    pub balance: u64,
+}
+
+/// Request `amount_sol` of test SOL for `user` and wait until it's confirmed
+pub async fn request_airdrop(
+    rpc: &RpcClient,
+    network: &str,
+    user: &Pubkey,
+    amount_sol: f64,
+) -> Result<Airdrop, AirdropError> {
+    if !TEST_NETWORKS.contains(&network) {
+        return Err(AirdropError::Failed(format!("Airdrops only exist on {}, not {}", TEST_NETWORKS.join(" / "), network)));
+    }
+    if !(amount_sol > 0.0 && amount_sol.is_finite()) {
+        return Err(AirdropError::Failed("Airdrop amount must be greater than 0".to_string()));
+    }
+    // A local validator's faucet has no cap
+    if network != "localnet" && amount_sol > MAX_AIRDROP_SOL {
+        return Err(AirdropError::Failed(format!("The {} faucet gives at most {} SOL per request", network, MAX_AIRDROP_SOL)));
+    }
+    let lamports = (amount_sol * LAMPORTS_PER_SOL as f64) as u64;
+
+    let signature = rpc.request_airdrop(user, lamports).await.map_err(|e| {
+        let text = e.to_string();
+        let lower = text.to_lowercase();
+        if RATE_LIMIT_HINTS.iter().any(|h| lower.contains(h)) {
+            AirdropError::RateLimited(format!(
+                "The {} faucet is rate-limiting requests right now. Try again later, ask for less, or use https://faucet.solana.com",
+                network
+            ))
+        } else {
+            AirdropError::Failed(format!("Airdrop request failed: {}", text))
+        }
+    })?;
+
+    let started = tokio::time::Instant::now();
+    loop {
+        let status = rpc.get_signature_status_with_commitment(&signature, CommitmentConfig::confirmed()).await
+            .map_err(|e| AirdropError::Failed(format!("Failed to check airdrop {}: {}", signature, e)))?;
+        match status {
+            Some(Ok(())) => break,
+            Some(Err(e)) => return Err(AirdropError::Failed(format!("Airdrop {} failed: {}", signature, e))),
+            None if started.elapsed() > CONFIRM_TIMEOUT => return Err(AirdropError::Failed(format!(
+                "Airdrop {} wasn't confirmed within {}s; check your balance in a moment",
+                signature, CONFIRM_TIMEOUT.as_secs()
+            ))),
+            None => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+
+    let balance = rpc.get_balance(user).await
+        .map_err(|e| AirdropError::Failed(format!("Airdrop confirmed but fetching the balance failed: {}", e)))?;
+    Ok(Airdrop { signature, lamports, balance })
+}
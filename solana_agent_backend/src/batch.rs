@@ -0,0 +1,298 @@
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::cleanup::pack_groups;
+use crate::token::{self, with_program};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── BATCH TRANSFERS ─────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// One payout to many recipients. Recipients come from the prompt or an
+// uploaded CSV (`address[,amount]` per line). Each recipient's instructions
+// (idempotent ATA create + transfer) stay in one transaction; transactions
+// are packed as full as the packet size allows.
+
+/// Upper bound on recipients per request
+pub const MAX_BATCH_RECIPIENTS: usize = 500;
+
+/// How many bad CSV rows to quote back before truncating
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// One validated recipient
+pub struct Payout {
+    pub recipient: Pubkey,
+    pub amount: f64,
+}
+
+/// Recipients after validation, plus the entries dropped as duplicates
+pub struct PayoutList {
+    pub payouts: Vec<Payout>,
+    pub duplicates: Vec<String>,
+}
+
+/// A line of the per-recipient manifest
+#[derive(Serialize, Debug)]
+pub struct ManifestEntry {
+    pub recipient: String,
+    pub amount: f64,
+    pub amount_atomic: u64,
+    /// Recipient's associated token account (SPL only)
+    pub token_account: Option<String>,
+    /// Index into the ordered transaction list
+    pub tx_index: usize,
+}
+
+pub struct BatchPlan {
+    pub transactions: Vec<String>,
+    pub manifest: Vec<ManifestEntry>,
+    pub total_atomic: u64,
+    pub decimals: u8,
+    pub mint: Option<Pubkey>,
+}
+
+/// Merge prompt recipients and CSV rows into one validated, deduplicated
+/// list. Rows without an amount use `default_amount`.
+pub fn parse_recipients(listed: &[String], csv: Option<&str>, default_amount: f64) -> Result<PayoutList, String> {
+    let mut rows: Vec<(String, String, Option<String>)> = listed.iter()
+        .enumerate()
+        .map(|(i, r)| (format!("recipient {}", i + 1), r.trim().to_string(), None))
+        .collect();
+
+    if let Some(csv) = csv {
+        let mut first_row = true;
+        for (i, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split([',', ';', '\t']).map(|f| f.trim().trim_matches('"'));
+            let address = fields.next().unwrap_or_default().to_string();
+            let amount = fields.next().filter(|a| !a.is_empty()).map(str::to_string);
+            // Skip a header row such as "address,amount"
+            let is_first = std::mem::replace(&mut first_row, false);
+            if is_first && Pubkey::from_str(&address).is_err() && amount.as_deref().is_none_or(|a| a.parse::<f64>().is_err()) {
+                continue;
+            }
+            rows.push((format!("line {}", i + 1), address, amount));
+        }
+    }
+
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    let mut payouts = vec![];
+    let mut duplicates = vec![];
+    for (origin, address, amount) in rows {
+        let recipient = match Pubkey::from_str(&address) {
+            Ok(p) => p,
+            Err(_) => {
+                errors.push(format!("{}: invalid address '{}'", origin, address));
+                continue;
+            },
+        };
+        let amount = match amount.map(|a| a.parse::<f64>()) {
+            None => default_amount,
+            Some(Ok(a)) => a,
+            Some(Err(_)) => {
+                errors.push(format!("{}: invalid amount", origin));
+                continue;
+            },
+        };
+        if !(amount > 0.0 && amount.is_finite()) {
+            errors.push(format!("{}: amount must be greater than 0", origin));
+            continue;
+        }
+        if !seen.insert(recipient) {
+            duplicates.push(recipient.to_string());
+            continue;
+        }
+        payouts.push(Payout { recipient, amount });
+    }
+
+    if !errors.is_empty() {
+        let more = errors.len().saturating_sub(MAX_REPORTED_ERRORS);
+        errors.truncate(MAX_REPORTED_ERRORS);
+        return Err(format!(
+            "Fix these recipients first: {}{}",
+            errors.join("; "),
+            if more > 0 { format!(" (and {} more)", more) } else { String::new() }
+        ));
+    }
+    if payouts.is_empty() {
+        return Err("No recipients given. List addresses or upload a CSV of address,amount".to_string());
+    }
+    if payouts.len() > MAX_BATCH_RECIPIENTS {
+        return Err(format!("Too many recipients ({}, max {})", payouts.len(), MAX_BATCH_RECIPIENTS));
+    }
+    Ok(PayoutList { payouts, duplicates })
+}
+
+/// Build the ordered transactions for `payouts` of `token` (SOL, a registry
+/// symbol, or a mint address). Fails up front if the sender can't cover the total.
+pub async fn build_batch_transfer(
+    rpc: &RpcClient,
+    sender: &Pubkey,
+    token: &str,
+    payouts: &[Payout],
+) -> Result<BatchPlan, String> {
+    let is_sol = token.trim().eq_ignore_ascii_case("SOL");
+    let (mint, decimals, program) = if is_sol {
+        (None, 9, None)
+    } else {
+        let mint = token::resolve_mint(token)?;
+        let info = token::fetch_mint(rpc, &mint).await?;
+        (Some(mint), info.decimals, Some(info.program))
+    };
+
+    let mut groups: Vec<Vec<Instruction>> = Vec::with_capacity(payouts.len());
+    let mut manifest = Vec::with_capacity(payouts.len());
+    let mut total_atomic = 0u64;
+    for payout in payouts {
+        let amount_atomic = token::to_atomic(payout.amount, decimals)?;
+        if amount_atomic == 0 {
+            return Err(format!("{} is below the smallest unit of {}", payout.amount, token));
+        }
+        total_atomic = total_atomic.checked_add(amount_atomic)
+            .ok_or_else(|| "Batch total overflows".to_string())?;
+
+        let (group, token_account) = match (mint, program) {
+            (Some(mint), Some(program)) => {
+                let source = get_associated_token_address_with_program_id(sender, &mint, &program);
+                let destination = get_associated_token_address_with_program_id(&payout.recipient, &mint, &program);
+                let transfer = spl_token::instruction::transfer_checked(
+                    &spl_token::id(), &source, &mint, &destination, sender, &[], amount_atomic, decimals,
+                ).map_err(|e| format!("Failed to build transfer ix: {}", e))?;
+                (
+                    vec![
+                        create_associated_token_account_idempotent(sender, &payout.recipient, &mint, &program),
+                        with_program(transfer, &program),
+                    ],
+                    Some(destination.to_string()),
+                )
+            },
+            _ => (vec![system_instruction::transfer(sender, &payout.recipient, amount_atomic)], None),
+        };
+        groups.push(group);
+        manifest.push(ManifestEntry {
+            recipient: payout.recipient.to_string(),
+            amount: payout.amount,
+            amount_atomic,
+            token_account,
+            tx_index: 0,
+        });
+    }
+
+    // Check funds before handing over dozens of transactions that would fail
+    match (mint, program) {
+        (Some(mint), Some(program)) => {
+            let source = get_associated_token_address_with_program_id(sender, &mint, &program);
+            let balance: u64 = rpc.get_token_account_balance(&source).await
+                .map_err(|_| format!("You don't hold any {}", token))?
+                .amount
+                .parse()
+                .map_err(|e| format!("Invalid token balance: {}", e))?;
+            if balance < total_atomic {
+                return Err(format!(
+                    "Batch needs {} {} but you hold {}",
+                    total_atomic as f64 / 10f64.powi(decimals as i32),
+                    token,
+                    balance as f64 / 10f64.powi(decimals as i32)
+                ));
+            }
+        },
+        _ => {
+            let balance = rpc.get_balance(sender).await
+                .map_err(|e| format!("Failed to fetch balance: {}", e))?;
+            if balance < total_atomic {
+                return Err(format!(
+                    "Batch needs {} SOL but you hold {}",
+                    total_atomic as f64 / LAMPORTS_PER_SOL as f64,
+                    balance as f64 / LAMPORTS_PER_SOL as f64
+                ));
+            }
+        },
+    }
+
+    let (transactions, placement) = pack_groups(&groups, sender)?;
+    for (entry, tx_index) in manifest.iter_mut().zip(placement) {
+        entry.tx_index = tx_index;
+    }
+
+    Ok(BatchPlan { transactions, manifest, total_atomic, decimals, mint })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(n: usize) -> Vec<String> {
+        (0..n).map(|_| Pubkey::new_unique().to_string()).collect()
+    }
+
+    fn error_of(result: Result<PayoutList, String>) -> String {
+        result.err().expect("expected an error")
+    }
+
+    #[test]
+    fn skips_a_header_row() {
+        let [a, b] = <[String; 2]>::try_from(addresses(2)).unwrap();
+        let csv = format!("address,amount\n{},1.5\n{},2", a, b);
+        let list = parse_recipients(&[], Some(&csv), 1.0).unwrap();
+        assert_eq!(list.payouts.len(), 2);
+        assert_eq!(list.payouts[0].amount, 1.5);
+        assert_eq!(list.payouts[1].amount, 2.0);
+    }
+
+    #[test]
+    fn only_the_first_row_can_be_a_header() {
+        let a = Pubkey::new_unique();
+        let csv = format!("{},1\nwallet,amount", a);
+        assert!(error_of(parse_recipients(&[], Some(&csv), 1.0)).contains("line 2: invalid address 'wallet'"));
+    }
+
+    #[test]
+    fn a_bad_first_row_with_an_amount_is_not_a_header() {
+        let csv = "not-an-address,5";
+        assert!(error_of(parse_recipients(&[], Some(csv), 1.0)).contains("line 1: invalid address"));
+    }
+
+    #[test]
+    fn merges_listed_and_csv_and_drops_duplicates() {
+        let [a, b] = <[String; 2]>::try_from(addresses(2)).unwrap();
+        let csv = format!("# payroll\n{};3\n\n{}\t4", a, b);
+        let list = parse_recipients(std::slice::from_ref(&a), Some(&csv), 1.0).unwrap();
+        assert_eq!(list.payouts.len(), 2);
+        // The listed entry comes first and keeps the default amount
+        assert_eq!(list.payouts[0].amount, 1.0);
+        assert_eq!(list.payouts[1].recipient.to_string(), b);
+        assert_eq!(list.duplicates, vec![a]);
+    }
+
+    #[test]
+    fn truncates_reported_errors() {
+        let csv = (0..MAX_REPORTED_ERRORS + 3).map(|i| format!("bad{},1", i)).collect::<Vec<_>>().join("\n");
+        let e = error_of(parse_recipients(&[], Some(&csv), 1.0));
+        assert_eq!(e.matches("invalid address").count(), MAX_REPORTED_ERRORS);
+        assert!(e.ends_with("(and 3 more)"), "{}", e);
+    }
+
+    #[test]
+    fn rejects_bad_amounts_and_empty_lists() {
+        let a = Pubkey::new_unique();
+        assert!(error_of(parse_recipients(&[], Some(&format!("{},0", a)), 1.0)).contains("greater than 0"));
+        assert!(error_of(parse_recipients(&[], Some(&format!("{},abc", a)), 1.0)).contains("invalid amount"));
+        assert!(error_of(parse_recipients(&[], None, 1.0)).contains("No recipients"));
+    }
+}
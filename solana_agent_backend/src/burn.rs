@@ -0,0 +1,92 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::token::{fetch_mint, to_atomic, with_program};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── BURN ────────────────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// A built burn, echoed in `meta` so the user sees exactly what goes
+pub struct BurnPlan {
+    pub tx_base64: String,
+    pub token_account: Pubkey,
+    pub program: Pubkey,
+    pub amount_atomic: u64,
+    pub decimals: u8,
+    pub remaining: u64,
+    pub closes_account: bool,
+}
+
+/// Burn `amount` (UI units; `None` burns the whole balance) of `mint` from
+/// the owner's associated account with `burn_checked`, optionally closing
+/// the account when nothing is left.
+pub async fn build_burn_tx(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: Option<f64>,
+    close: bool,
+) -> Result<BurnPlan, String> {
+    let info = fetch_mint(rpc, mint).await?;
+    let (program, decimals) = (info.program, info.decimals);
+
+    let token_account = get_associated_token_address_with_program_id(owner, mint, &program);
+    let balance: u64 = rpc.get_token_account_balance(&token_account).await
+        .map_err(|_| format!("You don't hold any of {}", mint))?
+        .amount
+        .parse()
+        .map_err(|e| format!("Invalid token balance: {}", e))?;
+
+    let amount_atomic = match amount {
+        Some(ui) if ui > 0.0 => to_atomic(ui, decimals)?,
+        Some(_) => return Err("Amount to burn must be greater than 0".to_string()),
+        None => balance,
+    };
+    if amount_atomic == 0 {
+        return Err("Nothing to burn".to_string());
+    }
+    if amount_atomic > balance {
+        return Err(format!(
+            "Can't burn {} - you only hold {}",
+            amount_atomic as f64 / 10f64.powi(decimals as i32),
+            balance as f64 / 10f64.powi(decimals as i32)
+        ));
+    }
+    let remaining = balance - amount_atomic;
+    if close && remaining > 0 {
+        return Err("The account can only be closed when the whole balance is burned".to_string());
+    }
+
+    let mut instructions = vec![with_program(
+        spl_token::instruction::burn_checked(&spl_token::id(), &token_account, mint, owner, &[], amount_atomic, decimals)
+            .map_err(|e| format!("Failed to build burn ix: {}", e))?,
+        &program,
+    )];
+    if close {
+        instructions.push(with_program(
+            spl_token::instruction::close_account(&spl_token::id(), &token_account, owner, owner, &[])
+                .map_err(|e| format!("Failed to build close ix: {}", e))?,
+            &program,
+        ));
+    }
+
+    let tx = Transaction::new_unsigned(Message::new(&instructions, Some(owner)));
+    Ok(BurnPlan {
+        tx_base64: general_purpose::STANDARD.encode(
+            bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+        ),
+        token_account,
+        program,
+        amount_atomic,
+        decimals,
+        remaining,
+        closes_account: close,
+    })
+}
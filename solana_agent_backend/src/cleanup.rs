@@ -0,0 +1,232 @@
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::token::TOKEN_2022_ID;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── WALLET CLEANUP (CLOSE EMPTY TOKEN ACCOUNTS) ─────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Every token account holds ~0.002 SOL of rent. Closing the empty ones
+// returns it. Accounts that can't be closed by the owner - frozen, with a
+// foreign close authority, or holding withheld Token-2022 fees - are skipped.
+
+/// An empty account that will be closed
+#[derive(Serialize, Debug)]
+pub struct EmptyAccount {
+    pub address: String,
+    pub mint: String,
+    pub program: &'static str,
+    pub lamports: u64,
+}
+
+pub struct CleanupPlan {
+    /// Ordered transactions, each under the packet size limit
+    pub transactions: Vec<String>,
+    pub accounts: Vec<EmptyAccount>,
+    pub skipped: usize,
+    pub total_lamports: u64,
+}
+
+/// Find the user's empty SPL Token and Token-2022 accounts and batch their closes
+pub async fn build_cleanup(rpc: &RpcClient, user: &str) -> Result<CleanupPlan, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+
+    let mut accounts = vec![];
+    let mut instructions = vec![];
+    let mut skipped = 0;
+
+    for (program, label) in [(spl_token::id(), "spl-token"), (TOKEN_2022_ID, "token-2022")] {
+        let keyed = rpc.get_token_accounts_by_owner(&user_pub, TokenAccountsFilter::ProgramId(program)).await
+            .map_err(|e| format!("Failed to fetch {} accounts: {}", label, e))?;
+
+        for account in keyed {
+            let data = serde_json::to_value(&account.account.data).unwrap_or_default();
+            let info = &data["parsed"]["info"];
+            if info["tokenAmount"]["amount"].as_str() != Some("0") {
+                continue;
+            }
+            if !closable_by(info, user) {
+                skipped += 1;
+                continue;
+            }
+
+            let address = Pubkey::from_str(&account.pubkey)
+                .map_err(|e| format!("Invalid token account: {}", e))?;
+            instructions.push(close_ix(&program, &address, &user_pub)?);
+            accounts.push(EmptyAccount {
+                address: account.pubkey,
+                mint: info["mint"].as_str().unwrap_or_default().to_string(),
+                program: label,
+                lamports: account.account.lamports,
+            });
+        }
+    }
+
+    let total_lamports = accounts.iter().map(|a| a.lamports).sum();
+    Ok(CleanupPlan {
+        transactions: pack_transactions(&instructions, &user_pub)?,
+        accounts,
+        skipped,
+        total_lamports,
+    })
+}
+
+/// Frozen accounts, foreign close authorities and withheld transfer fees all
+/// make `close_account` fail for the owner
+fn closable_by(info: &serde_json::Value, owner: &str) -> bool {
+    if info["state"].as_str() == Some("frozen") {
+        return false;
+    }
+    if info["closeAuthority"].as_str().is_some_and(|a| a != owner) {
+        return false;
+    }
+    let withheld = info["extensions"].as_array().into_iter().flatten()
+        .filter(|ext| ext["extension"].as_str() == Some("transferFeeAmount"))
+        .any(|ext| ext["state"]["withheldAmount"].as_u64().unwrap_or(0) > 0);
+    !withheld
+}
+
+/// `close_account` has the same layout in both token programs; only the
+/// program id differs
+fn close_ix(program: &Pubkey, account: &Pubkey, owner: &Pubkey) -> Result<Instruction, String> {
+    let mut ix = spl_token::instruction::close_account(&spl_token::id(), account, owner, owner, &[])
+        .map_err(|e| format!("Failed to build close ix: {}", e))?;
+    ix.program_id = *program;
+    Ok(ix)
+}
+
+/// Greedily fill transactions with instructions, starting a new one whenever
+/// the next instruction would push the serialized size past a packet
+pub fn pack_transactions(instructions: &[Instruction], payer: &Pubkey) -> Result<Vec<String>, String> {
+    let groups: Vec<Vec<Instruction>> = instructions.iter().map(|ix| vec![ix.clone()]).collect();
+    pack_groups(&groups, payer).map(|(transactions, _)| transactions)
+}
+
+/// Like `pack_transactions`, but keeps each group of instructions together
+/// in one transaction. Also returns which transaction each group landed in.
+pub fn pack_groups(groups: &[Vec<Instruction>], payer: &Pubkey) -> Result<(Vec<String>, Vec<usize>), String> {
+    let mut batches: Vec<Vec<Instruction>> = vec![];
+    let mut current: Vec<Instruction> = vec![];
+    let mut placement = Vec::with_capacity(groups.len());
+
+    for group in groups {
+        current.extend(group.iter().cloned());
+        if tx_size(&current, payer)? > PACKET_DATA_SIZE {
+            current.truncate(current.len() - group.len());
+            if !current.is_empty() {
+                batches.push(std::mem::take(&mut current));
+            }
+            current.extend(group.iter().cloned());
+            if tx_size(&current, payer)? > PACKET_DATA_SIZE {
+                return Err("A single instruction group is too large for one transaction".to_string());
+            }
+        }
+        placement.push(batches.len());
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    let transactions = batches.iter().map(|batch| {
+        let tx = Transaction::new_unsigned(Message::new(batch, Some(payer)));
+        Ok(general_purpose::STANDARD.encode(
+            bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+        ))
+    }).collect::<Result<Vec<_>, String>>()?;
+    Ok((transactions, placement))
+}
+
+fn tx_size(instructions: &[Instruction], payer: &Pubkey) -> Result<usize, String> {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    bincode::serialized_size(&tx)
+        .map(|n| n as usize)
+        .map_err(|e| format!("Serialize error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: Pubkey = solana_sdk::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+    fn ix(data_len: usize) -> Instruction {
+        Instruction::new_with_bytes(PROGRAM, &vec![7; data_len], vec![])
+    }
+
+    /// Data length that makes `[ix(first), ix(len)]` exactly one packet
+    fn filler_for(first: usize, payer: &Pubkey) -> usize {
+        (0..PACKET_DATA_SIZE)
+            .find(|&len| tx_size(&[ix(first), ix(len)], payer).unwrap() == PACKET_DATA_SIZE)
+            .expect("some length fills the packet exactly")
+    }
+
+    fn instruction_counts(txs: &[String]) -> Vec<usize> {
+        txs.iter().map(|tx| {
+            let tx: Transaction = bincode::deserialize(&general_purpose::STANDARD.decode(tx).unwrap()).unwrap();
+            tx.message.instructions.len()
+        }).collect()
+    }
+
+    #[test]
+    fn fills_a_packet_exactly() {
+        let payer = Pubkey::new_unique();
+        let fill = filler_for(200, &payer);
+        let txs = pack_transactions(&[ix(200), ix(fill)], &payer).unwrap();
+        assert_eq!(instruction_counts(&txs), vec![2]);
+    }
+
+    #[test]
+    fn one_byte_over_starts_a_new_transaction() {
+        let payer = Pubkey::new_unique();
+        let fill = filler_for(200, &payer);
+        let txs = pack_transactions(&[ix(200), ix(fill + 1), ix(10)], &payer).unwrap();
+        assert_eq!(instruction_counts(&txs), vec![1, 2]);
+    }
+
+    #[test]
+    fn rejects_an_instruction_larger_than_a_packet() {
+        let payer = Pubkey::new_unique();
+        assert!(pack_transactions(&[ix(10), ix(PACKET_DATA_SIZE)], &payer).is_err());
+    }
+
+    #[test]
+    fn nothing_to_pack() {
+        assert!(pack_transactions(&[], &Pubkey::new_unique()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn keeps_groups_together() {
+        let payer = Pubkey::new_unique();
+        let (txs, placement) = pack_groups(&[vec![ix(500)], vec![ix(300), ix(300)], vec![ix(100)]], &payer).unwrap();
+        assert_eq!(instruction_counts(&txs), vec![1, 3]);
+        assert_eq!(placement, vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn group_placement_at_the_packet_boundary() {
+        let payer = Pubkey::new_unique();
+        let fill = filler_for(200, &payer);
+        let (_, placement) = pack_groups(&[vec![ix(200)], vec![ix(fill)]], &payer).unwrap();
+        assert_eq!(placement, vec![0, 0]);
+        let (_, placement) = pack_groups(&[vec![ix(200)], vec![ix(fill + 1)]], &payer).unwrap();
+        assert_eq!(placement, vec![0, 1]);
+    }
+
+    #[test]
+    fn rejects_a_group_larger_than_a_packet() {
+        let payer = Pubkey::new_unique();
+        assert!(pack_groups(&[vec![ix(10)], vec![ix(700), ix(700)]], &payer).is_err());
+    }
+}
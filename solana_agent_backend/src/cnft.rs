@@ -0,0 +1,295 @@
+use mpl_bubblegum::accounts::TreeConfig;
+use mpl_bubblegum::instructions::{CreateTreeConfigBuilder, MintV1Builder};
+use mpl_bubblegum::types::{Creator, MetadataArgs, TokenProgramVersion, TokenStandard};
+use mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    message::Message,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::nonce::NonceInfo;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── COMPRESSED NFTS (BUBBLEGUM) ─────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Compressed NFTs live as leaves in a concurrent Merkle tree owned by the
+// operator. The tree's rent is paid once up front; after that each mint only
+// costs the transaction fee, which is what makes mass mints affordable.
+
+/// (max_depth, max_buffer_size) pairs accepted by spl-account-compression
+const VALID_TREE_SHAPES: &[(u32, u32)] = &[
+    (3, 8), (5, 8), (14, 64), (14, 256), (14, 1024), (14, 2048),
+    (15, 64), (16, 64), (17, 64), (17, 256), (17, 1024), (17, 2048),
+    (18, 64), (18, 256), (18, 1024), (18, 2048), (19, 64), (19, 256),
+    (19, 1024), (19, 2048), (20, 64), (20, 256), (20, 1024), (20, 2048),
+    (24, 64), (24, 256), (24, 512), (24, 1024), (24, 2048),
+    (26, 512), (26, 1024), (26, 2048), (30, 512), (30, 1024), (30, 2048),
+];
+
+/// Base fee per signature; cNFT mints carry two (user + operator)
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Account size and cost of a tree shape
+#[derive(Serialize, Debug)]
+pub struct TreeSizing {
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    pub canopy_depth: u32,
+    pub capacity: u64,
+    pub account_size: usize,
+    pub rent_lamports: u64,
+    /// Rent spread over every leaf plus the mint transaction fee
+    pub cost_per_mint_lamports: f64,
+}
+
+/// Rent spread over every leaf plus the mint transaction fee
+fn cost_per_mint(rent_lamports: u64, capacity: u64) -> f64 {
+    rent_lamports as f64 / capacity as f64 + (2 * LAMPORTS_PER_SIGNATURE) as f64
+}
+
+/// Bytes needed for a concurrent Merkle tree account (header + tree + canopy)
+pub fn tree_account_size(max_depth: u32, max_buffer_size: u32, canopy_depth: u32) -> usize {
+    let depth = max_depth as usize;
+    let header = 2 + 54;
+    let change_log = 40 + 32 * depth;
+    let rightmost_path = 40 + 32 * depth;
+    let tree = 24 + max_buffer_size as usize * change_log + rightmost_path;
+    let canopy = ((1usize << (canopy_depth + 1)) - 2) * 32;
+    header + tree + canopy
+}
+
+/// Validate a tree shape and price it
+pub async fn size_tree(
+    rpc: &RpcClient,
+    max_depth: u32,
+    max_buffer_size: u32,
+    canopy_depth: u32,
+) -> Result<TreeSizing, String> {
+    if !VALID_TREE_SHAPES.contains(&(max_depth, max_buffer_size)) {
+        return Err(format!("Unsupported tree shape depth={} buffer={}", max_depth, max_buffer_size));
+    }
+    if canopy_depth >= max_depth {
+        return Err(format!("Canopy depth must be below max depth ({})", max_depth));
+    }
+
+    let account_size = tree_account_size(max_depth, max_buffer_size, canopy_depth);
+    let rent_lamports = rpc.get_minimum_balance_for_rent_exemption(account_size).await
+        .map_err(|e| format!("Failed to fetch tree rent: {}", e))?;
+    let tree_config_rent = rpc.get_minimum_balance_for_rent_exemption(TreeConfig::LEN).await
+        .map_err(|e| format!("Failed to fetch tree config rent: {}", e))?;
+
+    let capacity = 1u64 << max_depth;
+    let total_rent = rent_lamports + tree_config_rent;
+    Ok(TreeSizing {
+        max_depth,
+        max_buffer_size,
+        canopy_depth,
+        capacity,
+        account_size,
+        rent_lamports: total_rent,
+        cost_per_mint_lamports: cost_per_mint(total_rent, capacity),
+    })
+}
+
+/// Create a tree owned by the operator and send it. The operator pays rent
+/// and is the tree creator, so it keeps the tree private.
+pub async fn create_tree(
+    rpc: &RpcClient,
+    operator: &Keypair,
+    sizing: &TreeSizing,
+) -> Result<Pubkey, String> {
+    let tree_kp = Keypair::new();
+    let tree = tree_kp.pubkey();
+    let (tree_config, _) = TreeConfig::find_pda(&tree);
+    let tree_rent = rpc.get_minimum_balance_for_rent_exemption(sizing.account_size).await
+        .map_err(|e| format!("Failed to fetch tree rent: {}", e))?;
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &operator.pubkey(),
+            &tree,
+            tree_rent,
+            sizing.account_size as u64,
+            &SPL_ACCOUNT_COMPRESSION_ID,
+        ),
+        CreateTreeConfigBuilder::new()
+            .tree_config(tree_config)
+            .merkle_tree(tree)
+            .payer(operator.pubkey())
+            .tree_creator(operator.pubkey())
+            .max_depth(sizing.max_depth)
+            .max_buffer_size(sizing.max_buffer_size)
+            .public(false)
+            .instruction(),
+    ];
+
+    let blockhash = rpc.get_latest_blockhash().await
+        .map_err(|e| format!("Failed to fetch blockhash: {}", e))?;
+    let tx = Transaction::new_signed_with_payer(&instructions, Some(&operator.pubkey()), &[operator, &tree_kp], blockhash);
+
+    rpc.send_and_confirm_transaction(&tx).await
+        .map_err(|e| format!("Tree creation failed: {}", e))?;
+
+    Ok(tree)
+}
+
+/// On-chain mint count of a tree
+pub async fn fetch_num_minted(rpc: &RpcClient, tree: &Pubkey) -> Result<u64, String> {
+    let (tree_config, _) = TreeConfig::find_pda(tree);
+    let data = rpc.get_account_data(&tree_config).await
+        .map_err(|e| format!("Failed to fetch tree config: {}", e))?;
+    let config = TreeConfig::from_bytes(&data)
+        .map_err(|e| format!("Invalid tree config: {}", e))?;
+    Ok(config.num_minted)
+}
+
+/// A freshly built compressed mint
+pub struct CnftMint {
+    pub tx_base64: String,
+    pub tree: Pubkey,
+    /// Leaf the mint will occupy if no other mint lands first
+    pub leaf_index: u64,
+    pub asset_id: Pubkey,
+}
+
+/// Leaf metadata for a user's cNFT. The creator entry is left unverified:
+/// verifying it would need the creator as an extra signer account.
+pub fn cnft_metadata(creator: &Pubkey, name: &str, symbol: &str, uri: &str) -> MetadataArgs {
+    MetadataArgs {
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+        seller_fee_basis_points: 0,
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        token_program_version: TokenProgramVersion::Original,
+        creators: vec![Creator { address: *creator, verified: false, share: 100 }],
+    }
+}
+
+/// Build a Bubblegum `mint_v1` into `tree` for the user. The operator is the
+/// tree creator and partially signs; the user is payer, owner and delegate.
+pub async fn build_mint_cnft_tx(
+    rpc: &RpcClient,
+    operator: &Keypair,
+    tree: &Pubkey,
+    user: &Pubkey,
+    metadata: MetadataArgs,
+    nonce: Option<&NonceInfo>,
+) -> Result<CnftMint, String> {
+    let user_pub = *user;
+    let (tree_config, _) = TreeConfig::find_pda(tree);
+    let leaf_index = fetch_num_minted(rpc, tree).await?;
+
+    let ix = MintV1Builder::new()
+        .tree_config(tree_config)
+        .leaf_owner(user_pub)
+        .leaf_delegate(user_pub)
+        .merkle_tree(*tree)
+        .payer(user_pub)
+        .tree_creator_or_delegate(operator.pubkey())
+        .metadata(metadata)
+        .instruction();
+
+    let (msg, blockhash) = match nonce {
+        Some(info) => (
+            Message::new_with_nonce(vec![ix], Some(&user_pub), &info.address, &info.authority),
+            info.blockhash,
+        ),
+        None => (
+            Message::new(&[ix], Some(&user_pub)),
+            rpc.get_latest_blockhash().await
+                .map_err(|e| format!("Failed to fetch blockhash: {}", e))?,
+        ),
+    };
+
+    let mut tx = Transaction::new_unsigned(msg);
+    tx.try_partial_sign(&[operator], blockhash)
+        .map_err(|e| format!("Failed to sign with operator keypair: {}", e))?;
+
+    Ok(CnftMint {
+        tx_base64: general_purpose::STANDARD.encode(
+            bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+        ),
+        tree: *tree,
+        leaf_index,
+        asset_id: mpl_bubblegum::utils::get_asset_id(tree, leaf_index),
+    })
+}
+
+// ─── TREE REGISTRY ──────────────────────────────────────────
+
+/// A tree the operator created, persisted so it survives restarts
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TreeRecord {
+    pub network: String,
+    pub address: String,
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    pub canopy_depth: u32,
+    pub capacity: u64,
+    pub rent_lamports: u64,
+}
+
+/// Operator trees, stored as JSON in DATA_DIR/cnft_trees.json
+pub struct TreeRegistry {
+    path: PathBuf,
+    trees: Mutex<Vec<TreeRecord>>,
+}
+
+impl TreeRecord {
+    pub fn cost_per_mint_lamports(&self) -> f64 {
+        cost_per_mint(self.rent_lamports, self.capacity)
+    }
+}
+
+impl TreeRegistry {
+    pub fn load(path: PathBuf) -> Self {
+        let trees = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        TreeRegistry { path, trees: Mutex::new(trees) }
+    }
+
+    pub fn list(&self, network: &str) -> Vec<TreeRecord> {
+        self.trees.lock().unwrap().iter().filter(|t| t.network == network).cloned().collect()
+    }
+
+    pub fn add(&self, record: TreeRecord) -> Result<(), String> {
+        let mut trees = self.trees.lock().unwrap();
+        trees.push(record);
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+        }
+        let json = serde_json::to_vec_pretty(&*trees).map_err(|e| format!("Serialize error: {}", e))?;
+        std::fs::write(&self.path, json).map_err(|e| format!("Failed to save tree registry: {}", e))
+    }
+
+    /// Most recently created tree on `network` that still has free leaves
+    pub async fn pick_tree(&self, rpc: &RpcClient, network: &str) -> Result<(TreeRecord, Pubkey), String> {
+        for record in self.list(network).into_iter().rev() {
+            let tree = Pubkey::from_str(&record.address)
+                .map_err(|e| format!("Invalid tree address in registry: {}", e))?;
+            if fetch_num_minted(rpc, &tree).await? < record.capacity {
+                return Ok((record, tree));
+            }
+        }
+        Err(format!("No compressed NFT tree with free capacity on {}. Ask an admin to create one.", network))
+    }
+}
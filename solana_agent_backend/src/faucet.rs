@@ -0,0 +1,306 @@
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::instructions::CreateMetadataAccountV3Builder;
+use mpl_token_metadata::types::DataV2;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    message::Message,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::nonce::NonceInfo;
+use crate::rpc::TEST_NETWORKS;
+use crate::swap;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── TEST TOKEN FAUCET ───────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Mainnet mints don't exist on devnet, so each registry token gets an
+// operator-owned stand-in there (test-USDC, test-BONK, ...) with the same
+// decimals. The operator is its mint authority: a FAUCET request builds a
+// mint to the user's ATA that the operator partially signs. Devnet
+// transfers of registry tokens then move real balances of these mints.
+
+/// Used when the user doesn't say how much
+pub const DEFAULT_FAUCET_AMOUNT: f64 = 100.0;
+
+/// Largest single faucet mint, in UI units
+const MAX_FAUCET_AMOUNT: f64 = 1_000_000.0;
+
+/// One faucet mint per wallet and token, and per client IP and token, this
+/// often. Wallets are free to make, so the IP cooldown is what actually
+/// limits one client.
+const FAUCET_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// A devnet stand-in for a registry token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TestMint {
+    pub network: String,
+    pub symbol: String,
+    pub mint: String,
+    pub decimals: u8,
+}
+
+/// A built faucet mint
+pub struct FaucetMint {
+    pub tx_base64: String,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub amount_atomic: u64,
+    pub decimals: u8,
+}
+
+/// Operator test mints, stored as JSON in DATA_DIR/test_mints.json
+pub struct TestMints {
+    path: PathBuf,
+    mints: Mutex<Vec<TestMint>>,
+    /// Serializes mint creation so two first requests don't make two mints
+    creating: tokio::sync::Mutex<()>,
+    last_drip: Mutex<HashMap<(String, String), Instant>>,
+}
+
+impl TestMints {
+    pub fn load(path: PathBuf) -> Self {
+        let mints = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        TestMints {
+            path,
+            mints: Mutex::new(mints),
+            creating: tokio::sync::Mutex::new(()),
+            last_drip: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn list(&self, network: &str) -> Vec<TestMint> {
+        self.mints.lock().unwrap().iter().filter(|m| m.network == network).cloned().collect()
+    }
+
+    /// The test mint standing in for `symbol` on `network`, if one was created
+    pub fn get(&self, network: &str, symbol: &str) -> Option<Pubkey> {
+        let symbol = symbol.to_uppercase();
+        self.mints.lock().unwrap().iter()
+            .find(|m| m.network == network && m.symbol == symbol)
+            .and_then(|m| Pubkey::from_str(&m.mint).ok())
+    }
+
+    /// The registry symbol a test mint stands in for
+    pub fn symbol_for(&self, network: &str, mint: &str) -> Option<String> {
+        self.mints.lock().unwrap().iter()
+            .find(|m| m.network == network && m.mint == mint)
+            .map(|m| m.symbol.clone())
+    }
+
+    fn add(&self, record: TestMint) -> Result<(), String> {
+        let mut mints = self.mints.lock().unwrap();
+        mints.push(record);
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+        }
+        let json = serde_json::to_vec_pretty(&*mints).map_err(|e| format!("Serialize error: {}", e))?;
+        std::fs::write(&self.path, json).map_err(|e| format!("Failed to save test mints: {}", e))
+    }
+
+    /// The test mint for `symbol`, creating it on first use
+    pub async fn ensure(&self, rpc: &RpcClient, operator: &Keypair, network: &str, symbol: &str) -> Result<Pubkey, String> {
+        if let Some(mint) = self.get(network, symbol) {
+            return Ok(mint);
+        }
+        let _guard = self.creating.lock().await;
+        if let Some(mint) = self.get(network, symbol) {
+            return Ok(mint);
+        }
+
+        let symbol = symbol.to_uppercase();
+        let decimals = swap::token_decimals(&symbol);
+        let mint = create_test_mint(rpc, operator, &symbol, decimals).await?;
+        println!("[FAUCET] Created test {} on {}: {}", symbol, network, mint);
+        self.add(TestMint { network: network.to_string(), symbol, mint: mint.to_string(), decimals })?;
+        Ok(mint)
+    }
+
+    /// Check that `symbol` can be dripped to `user` on `network` now and
+    /// return its test mint, creating the mint on first use
+    pub async fn prepare(
+        &self,
+        rpc: &RpcClient,
+        operator: &Keypair,
+        network: &str,
+        user: &Pubkey,
+        client_ip: Option<IpAddr>,
+        symbol: &str,
+    ) -> Result<Pubkey, String> {
+        if !TEST_NETWORKS.contains(&network) {
+            return Err(format!("The test-token faucet only runs on {}", TEST_NETWORKS.join(" / ")));
+        }
+        let symbol = symbol.to_uppercase();
+        if symbol == "SOL" || !swap::is_valid_token(&symbol) {
+            return Err(format!("No test token for '{}'. Try USDC, USDT, BONK, JUP, RAY or WIF (use an airdrop for SOL)", symbol));
+        }
+        let wait = self.cooldown_left(&drip_keys(network, user, client_ip, &symbol));
+        if !wait.is_zero() {
+            return Err(format!("You just got test {}. Try again in {}s", symbol, wait.as_secs() + 1));
+        }
+        self.ensure(rpc, operator, network, &symbol).await
+    }
+
+    /// Start the cooldown after a faucet transaction was handed out, whether
+    /// or not it gets signed
+    pub fn record_drip(&self, network: &str, user: &Pubkey, client_ip: Option<IpAddr>, symbol: &str) {
+        let mut last_drip = self.last_drip.lock().unwrap();
+        for key in drip_keys(network, user, client_ip, &symbol.to_uppercase()) {
+            last_drip.insert(key, Instant::now());
+        }
+    }
+
+    fn cooldown_left(&self, keys: &[(String, String)]) -> Duration {
+        let last_drip = self.last_drip.lock().unwrap();
+        keys.iter()
+            .filter_map(|key| last_drip.get(key))
+            .map(|at| FAUCET_COOLDOWN.saturating_sub(at.elapsed()))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Cooldown keys: the wallet, and the client IP when the request has one
+fn drip_keys(network: &str, user: &Pubkey, client_ip: Option<IpAddr>, symbol: &str) -> Vec<(String, String)> {
+    let scope = format!("{}:{}", network, symbol);
+    let mut keys = vec![(user.to_string(), scope.clone())];
+    if let Some(ip) = client_ip {
+        keys.push((format!("ip:{}", ip), scope));
+    }
+    keys
+}
+
+/// Build a mint of `amount` test `symbol` to the user's ATA. The user pays
+/// the fee; the operator partially signs as mint authority.
+pub async fn build_faucet_tx(
+    rpc: &RpcClient,
+    operator: &Keypair,
+    mint: &Pubkey,
+    user: &Pubkey,
+    symbol: &str,
+    amount: f64,
+    nonce: Option<&NonceInfo>,
+) -> Result<FaucetMint, String> {
+    if !(amount > 0.0 && amount <= MAX_FAUCET_AMOUNT) {
+        return Err(format!("Faucet amount must be between 0 and {}", MAX_FAUCET_AMOUNT));
+    }
+    let decimals = swap::token_decimals(symbol);
+    let amount_atomic = swap::to_atomic(symbol, amount);
+    let token_account = get_associated_token_address(user, mint);
+
+    let instructions = vec![
+        create_associated_token_account_idempotent(user, user, mint, &spl_token::id()),
+        spl_token::instruction::mint_to_checked(
+            &spl_token::id(), mint, &token_account, &operator.pubkey(), &[], amount_atomic, decimals,
+        ).map_err(|e| format!("Failed to build mint_to ix: {}", e))?,
+    ];
+
+    let (msg, blockhash) = match nonce {
+        Some(info) => (
+            Message::new_with_nonce(instructions, Some(user), &info.address, &info.authority),
+            info.blockhash,
+        ),
+        None => (
+            Message::new(&instructions, Some(user)),
+            rpc.get_latest_blockhash().await
+                .map_err(|e| format!("Failed to fetch blockhash: {}", e))?,
+        ),
+    };
+
+    let mut tx = Transaction::new_unsigned(msg);
+    tx.try_partial_sign(&[operator], blockhash)
+        .map_err(|e| format!("Failed to sign with operator keypair: {}", e))?;
+
+    Ok(FaucetMint {
+        tx_base64: general_purpose::STANDARD.encode(
+            bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+        ),
+        mint: *mint,
+        token_account,
+        amount_atomic,
+        decimals,
+    })
+}
+
+/// Create and send an operator-owned mint with "Test <SYMBOL>" metadata
+async fn create_test_mint(rpc: &RpcClient, operator: &Keypair, symbol: &str, decimals: u8) -> Result<Pubkey, String> {
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let authority = operator.pubkey();
+    let (metadata, _) = Metadata::find_pda(&mint);
+    let mint_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN).await
+        .map_err(|e| format!("Failed to fetch mint rent: {}", e))?;
+
+    let instructions = vec![
+        system_instruction::create_account(&authority, &mint, mint_rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), &mint, &authority, None, decimals)
+            .map_err(|e| format!("Failed to build initialize_mint ix: {}", e))?,
+        CreateMetadataAccountV3Builder::new()
+            .metadata(metadata)
+            .mint(mint)
+            .mint_authority(authority)
+            .payer(authority)
+            .update_authority(authority, true)
+            .data(DataV2 {
+                name: format!("Test {}", symbol),
+                symbol: format!("t{}", symbol),
+                uri: String::new(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            })
+            .is_mutable(true)
+            .instruction(),
+    ];
+
+    let blockhash = rpc.get_latest_blockhash().await
+        .map_err(|e| format!("Failed to fetch blockhash: {}", e))?;
+    let tx = Transaction::new_signed_with_payer(&instructions, Some(&authority), &[operator, &mint_kp], blockhash);
+    rpc.send_and_confirm_transaction(&tx).await
+        .map_err(|e| format!("Test mint creation failed: {}", e))?;
+    Ok(mint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cooldown_applies_per_wallet_and_per_ip() {
+        let mints = TestMints::load(PathBuf::from("/nonexistent/test_mints.json"));
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        mints.record_drip("devnet", &first, Some(ip), "usdc");
+
+        let left = |user: &Pubkey, ip: Option<IpAddr>, symbol: &str| mints.cooldown_left(&drip_keys("devnet", user, ip, symbol));
+        assert!(!left(&first, None, "USDC").is_zero());
+        // A fresh wallet from the same IP still waits
+        assert!(!left(&second, Some(ip), "USDC").is_zero());
+        // Other IPs, tokens and networks don't
+        assert!(left(&second, Some("203.0.113.8".parse().unwrap()), "USDC").is_zero());
+        assert!(left(&first, Some(ip), "BONK").is_zero());
+        assert!(mints.cooldown_left(&drip_keys("testnet", &first, Some(ip), "USDC")).is_zero());
+    }
+}
@@ -0,0 +1,545 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::token::TOKEN_2022_ID;
+use crate::wsol;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── LIQUIDITY PROVISION ─────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Each AMM sits behind `PoolAdapter`. Adapters read pool state on-chain,
+// size the deposit to the pool's current ratio, and bound both sides by
+// the caller's slippage so a moved pool fails the transaction instead of
+// filling at a bad price.
+
+/// Pool state common to every AMM, as reported in `meta`
+#[derive(Serialize, Debug, Clone)]
+pub struct PoolSummary {
+    pub amm: &'static str,
+    pub address: String,
+    pub mint_0: String,
+    pub mint_1: String,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub reserve_0: u64,
+    pub reserve_1: u64,
+    pub lp_mint: String,
+    pub lp_supply: u64,
+    /// Token 1 per token 0, in UI units
+    pub price: f64,
+}
+
+/// A built add/remove transaction with the amounts it was sized for
+#[derive(Serialize, Debug)]
+pub struct LpPlan {
+    #[serde(skip)]
+    pub tx_base64: String,
+    pub pool: PoolSummary,
+    pub lp_amount: u64,
+    /// Expected token amounts at the current pool ratio
+    pub amount_0: u64,
+    pub amount_1: u64,
+    /// Max spent (add) or min received (remove) after slippage
+    pub limit_0: u64,
+    pub limit_1: u64,
+    pub slippage_bps: u16,
+}
+
+#[async_trait]
+pub trait PoolAdapter: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Deepest pool for the pair
+    async fn find_pool(&self, rpc: &RpcClient, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Pubkey, String>;
+
+    /// Deposit `amount_in` of `mint_in` plus whatever of the other token the
+    /// pool ratio requires
+    async fn add_liquidity(
+        &self,
+        rpc: &RpcClient,
+        user: &Pubkey,
+        pool: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        slippage_bps: u16,
+    ) -> Result<LpPlan, String>;
+
+    /// Burn `percent` of the user's LP tokens for both underlying tokens
+    async fn remove_liquidity(
+        &self,
+        rpc: &RpcClient,
+        user: &Pubkey,
+        pool: &Pubkey,
+        percent: f64,
+        slippage_bps: u16,
+    ) -> Result<LpPlan, String>;
+}
+
+/// The adapter used for `network`
+pub fn adapter_for(network: &str) -> Box<dyn PoolAdapter> {
+    Box::new(RaydiumCpmm::for_network(network))
+}
+
+// ─── RAYDIUM CPMM ───────────────────────────────────────────
+
+const CPMM_MAINNET: Pubkey = solana_sdk::pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+const CPMM_DEVNET: Pubkey = solana_sdk::pubkey!("CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW");
+const AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+
+// PoolState is a packed zero-copy account; offsets include the 8-byte discriminator
+const TOKEN_0_VAULT: usize = 72;
+const TOKEN_1_VAULT: usize = 104;
+const LP_MINT: usize = 136;
+const TOKEN_0_MINT: usize = 168;
+const TOKEN_1_MINT: usize = 200;
+const TOKEN_0_PROGRAM: usize = 232;
+const TOKEN_1_PROGRAM: usize = 264;
+const STATUS: usize = 329;
+const MINT_0_DECIMALS: usize = 331;
+const MINT_1_DECIMALS: usize = 332;
+const LP_SUPPLY: usize = 333;
+const PROTOCOL_FEES_0: usize = 341;
+const PROTOCOL_FEES_1: usize = 349;
+const FUND_FEES_0: usize = 357;
+const FUND_FEES_1: usize = 365;
+
+/// Status bits that disable deposit / withdraw
+const STATUS_DEPOSIT_DISABLED: u8 = 1;
+const STATUS_WITHDRAW_DISABLED: u8 = 1 << 1;
+
+/// Raydium's constant-product AMM (supports Token-2022 mints)
+pub struct RaydiumCpmm {
+    program: Pubkey,
+}
+
+struct CpmmPool {
+    address: Pubkey,
+    vault_0: Pubkey,
+    vault_1: Pubkey,
+    lp_mint: Pubkey,
+    mint_0: Pubkey,
+    mint_1: Pubkey,
+    program_0: Pubkey,
+    program_1: Pubkey,
+    status: u8,
+    decimals_0: u8,
+    decimals_1: u8,
+    lp_supply: u64,
+    reserve_0: u64,
+    reserve_1: u64,
+}
+
+impl CpmmPool {
+    /// LP tokens `amount_in` of `mint_in` buys at the current ratio, and what
+    /// each side then costs
+    fn deposit_quote(&self, mint_in: &Pubkey, amount_in: u64) -> Result<(u64, u64, u64), String> {
+        // LP tokens the given side buys at the current ratio
+        let reserve_in = if *mint_in == self.mint_0 {
+            self.reserve_0
+        } else if *mint_in == self.mint_1 {
+            self.reserve_1
+        } else {
+            return Err(format!("{} is not in this pool", mint_in));
+        };
+        let lp_amount = mul_div(amount_in, self.lp_supply, reserve_in, false);
+        if lp_amount == 0 {
+            return Err("Deposit too small for this pool".to_string());
+        }
+
+        // The program rounds token amounts up for deposits
+        let amount_0 = mul_div(lp_amount, self.reserve_0, self.lp_supply, true);
+        let amount_1 = mul_div(lp_amount, self.reserve_1, self.lp_supply, true);
+        Ok((lp_amount, amount_0, amount_1))
+    }
+}
+
+impl RaydiumCpmm {
+    pub fn for_network(network: &str) -> Self {
+        let program = if network == "mainnet" { CPMM_MAINNET } else { CPMM_DEVNET };
+        RaydiumCpmm { program }
+    }
+
+    fn authority(&self) -> Pubkey {
+        Pubkey::find_program_address(&[AUTH_SEED], &self.program).0
+    }
+
+    async fn load(&self, rpc: &RpcClient, address: &Pubkey) -> Result<CpmmPool, String> {
+        let account = rpc.get_account(address).await
+            .map_err(|e| format!("Failed to fetch pool {}: {}", address, e))?;
+        if account.owner != self.program {
+            return Err(format!("{} is not a Raydium CPMM pool", address));
+        }
+        let d = &account.data;
+        if d.len() < FUND_FEES_1 + 8 {
+            return Err(format!("Pool {} has an unexpected layout", address));
+        }
+
+        let vault_0 = read_pubkey(d, TOKEN_0_VAULT);
+        let vault_1 = read_pubkey(d, TOKEN_1_VAULT);
+        let vaults = rpc.get_multiple_accounts(&[vault_0, vault_1]).await
+            .map_err(|e| format!("Failed to fetch pool vaults: {}", e))?;
+        let vault_amount = |i: usize| vaults[i].as_ref()
+            .and_then(|a| a.data.get(64..72))
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| "Pool vault missing".to_string());
+
+        // Vaults also hold uncollected protocol and fund fees, which aren't liquidity
+        let reserve_0 = vault_amount(0)?.saturating_sub(read_u64(d, PROTOCOL_FEES_0) + read_u64(d, FUND_FEES_0));
+        let reserve_1 = vault_amount(1)?.saturating_sub(read_u64(d, PROTOCOL_FEES_1) + read_u64(d, FUND_FEES_1));
+
+        Ok(CpmmPool {
+            address: *address,
+            vault_0,
+            vault_1,
+            lp_mint: read_pubkey(d, LP_MINT),
+            mint_0: read_pubkey(d, TOKEN_0_MINT),
+            mint_1: read_pubkey(d, TOKEN_1_MINT),
+            program_0: read_pubkey(d, TOKEN_0_PROGRAM),
+            program_1: read_pubkey(d, TOKEN_1_PROGRAM),
+            status: d[STATUS],
+            decimals_0: d[MINT_0_DECIMALS],
+            decimals_1: d[MINT_1_DECIMALS],
+            lp_supply: read_u64(d, LP_SUPPLY),
+            reserve_0,
+            reserve_1,
+        })
+    }
+
+    /// Accounts shared by `deposit` and `withdraw`, in program order
+    fn liquidity_accounts(&self, user: &Pubkey, pool: &CpmmPool) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(self.authority(), false),
+            AccountMeta::new(pool.address, false),
+            AccountMeta::new(get_associated_token_address_with_program_id(user, &pool.lp_mint, &spl_token::id()), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(user, &pool.mint_0, &pool.program_0), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(user, &pool.mint_1, &pool.program_1), false),
+            AccountMeta::new(pool.vault_0, false),
+            AccountMeta::new(pool.vault_1, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            // The pool takes the Token-2022 program even for classic-token pairs
+            AccountMeta::new_readonly(TOKEN_2022_ID, false),
+            AccountMeta::new_readonly(pool.mint_0, false),
+            AccountMeta::new_readonly(pool.mint_1, false),
+            AccountMeta::new(pool.lp_mint, false),
+        ]
+    }
+
+    fn summary(&self, pool: &CpmmPool) -> PoolSummary {
+        let ui = |amount: u64, decimals: u8| amount as f64 / 10f64.powi(decimals as i32);
+        let reserve_0 = ui(pool.reserve_0, pool.decimals_0);
+        PoolSummary {
+            amm: self.name(),
+            address: pool.address.to_string(),
+            mint_0: pool.mint_0.to_string(),
+            mint_1: pool.mint_1.to_string(),
+            decimals_0: pool.decimals_0,
+            decimals_1: pool.decimals_1,
+            reserve_0: pool.reserve_0,
+            reserve_1: pool.reserve_1,
+            lp_mint: pool.lp_mint.to_string(),
+            lp_supply: pool.lp_supply,
+            price: if reserve_0 > 0.0 { ui(pool.reserve_1, pool.decimals_1) / reserve_0 } else { 0.0 },
+        }
+    }
+}
+
+#[async_trait]
+impl PoolAdapter for RaydiumCpmm {
+    fn name(&self) -> &'static str { "raydium-cpmm" }
+
+    async fn find_pool(&self, rpc: &RpcClient, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Pubkey, String> {
+        // Pools store their mints sorted
+        let (mint_0, mint_1) = if mint_a < mint_b { (mint_a, mint_b) } else { (mint_b, mint_a) };
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &anchor_discriminator("account:PoolState"))),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(TOKEN_0_MINT, mint_0.as_ref())),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(TOKEN_1_MINT, mint_1.as_ref())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let pools = rpc.get_program_accounts_with_config(&self.program, config).await
+            .map_err(|e| format!("Failed to search Raydium pools: {}", e))?;
+
+        // Several fee tiers can exist for one pair; go with the deepest
+        let mut best: Option<(Pubkey, u64)> = None;
+        for (address, _) in pools {
+            let pool = match self.load(rpc, &address).await {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+            if best.is_none_or(|(_, reserve)| pool.reserve_0 > reserve) {
+                best = Some((address, pool.reserve_0));
+            }
+        }
+        best.map(|(address, _)| address)
+            .ok_or_else(|| format!("No Raydium CPMM pool for {} / {}", mint_0, mint_1))
+    }
+
+    async fn add_liquidity(
+        &self,
+        rpc: &RpcClient,
+        user: &Pubkey,
+        pool_address: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        slippage_bps: u16,
+    ) -> Result<LpPlan, String> {
+        let pool = self.load(rpc, pool_address).await?;
+        if pool.status & STATUS_DEPOSIT_DISABLED != 0 {
+            return Err("Deposits are disabled on this pool".to_string());
+        }
+        if pool.lp_supply == 0 || pool.reserve_0 == 0 || pool.reserve_1 == 0 {
+            return Err("Pool has no liquidity yet; the first deposit sets its price".to_string());
+        }
+
+        let (lp_amount, amount_0, amount_1) = pool.deposit_quote(mint_in, amount_in)?;
+        let limit_0 = with_slippage(amount_0, slippage_bps, true);
+        let limit_1 = with_slippage(amount_1, slippage_bps, true);
+
+        let mut data = anchor_discriminator("global:deposit").to_vec();
+        data.extend_from_slice(&lp_amount.to_le_bytes());
+        data.extend_from_slice(&limit_0.to_le_bytes());
+        data.extend_from_slice(&limit_1.to_le_bytes());
+
+        let mut instructions = vec![
+            create_associated_token_account_idempotent(user, user, &pool.lp_mint, &spl_token::id()),
+        ];
+        instructions.extend(wrap_if_native(user, &pool.mint_0, limit_0)?);
+        instructions.extend(wrap_if_native(user, &pool.mint_1, limit_1)?);
+        instructions.push(Instruction {
+            program_id: self.program,
+            accounts: self.liquidity_accounts(user, &pool),
+            data,
+        });
+        instructions.extend(unwrap_if_native(rpc, user, &pool.mint_0, &pool.mint_1).await?);
+
+        Ok(LpPlan {
+            tx_base64: encode_tx(&instructions, user)?,
+            pool: self.summary(&pool),
+            lp_amount,
+            amount_0,
+            amount_1,
+            limit_0,
+            limit_1,
+            slippage_bps,
+        })
+    }
+
+    async fn remove_liquidity(
+        &self,
+        rpc: &RpcClient,
+        user: &Pubkey,
+        pool_address: &Pubkey,
+        percent: f64,
+        slippage_bps: u16,
+    ) -> Result<LpPlan, String> {
+        if !(percent > 0.0 && percent <= 100.0) {
+            return Err("Percentage to remove must be between 0 and 100".to_string());
+        }
+
+        let pool = self.load(rpc, pool_address).await?;
+        if pool.status & STATUS_WITHDRAW_DISABLED != 0 {
+            return Err("Withdrawals are disabled on this pool".to_string());
+        }
+
+        let lp_account = get_associated_token_address_with_program_id(user, &pool.lp_mint, &spl_token::id());
+        let held: u64 = rpc.get_token_account_balance(&lp_account).await
+            .map_err(|_| "You have no LP tokens for this pool".to_string())?
+            .amount
+            .parse()
+            .map_err(|e| format!("Invalid LP balance: {}", e))?;
+        let lp_amount = if percent >= 100.0 { held } else { (held as f64 * percent / 100.0) as u64 };
+        if lp_amount == 0 {
+            return Err("You have no LP tokens for this pool".to_string());
+        }
+
+        // ...and down for withdrawals
+        let amount_0 = mul_div(lp_amount, pool.reserve_0, pool.lp_supply, false);
+        let amount_1 = mul_div(lp_amount, pool.reserve_1, pool.lp_supply, false);
+        let limit_0 = with_slippage(amount_0, slippage_bps, false);
+        let limit_1 = with_slippage(amount_1, slippage_bps, false);
+
+        let mut data = anchor_discriminator("global:withdraw").to_vec();
+        data.extend_from_slice(&lp_amount.to_le_bytes());
+        data.extend_from_slice(&limit_0.to_le_bytes());
+        data.extend_from_slice(&limit_1.to_le_bytes());
+
+        let mut accounts = self.liquidity_accounts(user, &pool);
+        accounts.push(AccountMeta::new_readonly(spl_memo::id(), false));
+
+        let mut instructions = vec![
+            create_associated_token_account_idempotent(user, user, &pool.mint_0, &pool.program_0),
+            create_associated_token_account_idempotent(user, user, &pool.mint_1, &pool.program_1),
+            Instruction { program_id: self.program, accounts, data },
+        ];
+        instructions.extend(unwrap_if_native(rpc, user, &pool.mint_0, &pool.mint_1).await?);
+
+        Ok(LpPlan {
+            tx_base64: encode_tx(&instructions, user)?,
+            pool: self.summary(&pool),
+            lp_amount,
+            amount_0,
+            amount_1,
+            limit_0,
+            limit_1,
+            slippage_bps,
+        })
+    }
+}
+
+// ─── HELPERS ────────────────────────────────────────────────
+
+/// First 8 bytes of sha256("<namespace>:<name>")
+fn anchor_discriminator(preimage: &str) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&Sha256::digest(preimage.as_bytes())[..8]);
+    out
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap()
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// a * b / c in u128, rounded up or down
+fn mul_div(a: u64, b: u64, c: u64, round_up: bool) -> u64 {
+    let (a, b, c) = (a as u128, b as u128, c as u128);
+    let q = if round_up { (a * b).div_ceil(c) } else { a * b / c };
+    q.min(u64::MAX as u128) as u64
+}
+
+/// Raise a max (`up`) or lower a min by `bps`. A min never goes below 0.
+fn with_slippage(amount: u64, bps: u16, up: bool) -> u64 {
+    let bps = if up { bps } else { bps.min(10_000) };
+    let factor = if up { 10_000 + bps as u128 } else { 10_000 - bps as u128 };
+    (amount as u128 * factor / 10_000).min(u64::MAX as u128) as u64
+}
+
+/// Fund a wSOL account with `lamports` when the pool side is SOL
+fn wrap_if_native(user: &Pubkey, mint: &Pubkey, lamports: u64) -> Result<Vec<Instruction>, String> {
+    if *mint != wsol::wsol_mint() {
+        return Ok(vec![]);
+    }
+    wsol::wrap_ixs(user, lamports)
+}
+
+/// Close the wSOL account afterwards so leftovers come back as SOL, unless
+/// the user already held wSOL in it
+async fn unwrap_if_native(rpc: &RpcClient, user: &Pubkey, mint_0: &Pubkey, mint_1: &Pubkey) -> Result<Vec<Instruction>, String> {
+    let wsol_mint = wsol::wsol_mint();
+    if *mint_0 != wsol_mint && *mint_1 != wsol_mint {
+        return Ok(vec![]);
+    }
+    if wsol::has_wsol_account(rpc, user).await? {
+        return Ok(vec![]);
+    }
+    Ok(vec![wsol::unwrap_ix(user)?])
+}
+
+fn encode_tx(instructions: &[Instruction], payer: &Pubkey) -> Result<String, String> {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    Ok(general_purpose::STANDARD.encode(
+        bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounds_each_way() {
+        assert_eq!(mul_div(10, 10, 3, false), 33);
+        assert_eq!(mul_div(10, 10, 3, true), 34);
+        // Exact results don't round up
+        assert_eq!(mul_div(10, 9, 3, true), 30);
+    }
+
+    #[test]
+    fn mul_div_has_no_intermediate_overflow() {
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX, false), u64::MAX);
+        assert_eq!(mul_div(u64::MAX, 3, 4, false), u64::MAX / 4 * 3 + 2);
+        // A result above u64 saturates
+        assert_eq!(mul_div(u64::MAX, 2, 1, true), u64::MAX);
+    }
+
+    fn pool(lp_supply: u64, reserve_0: u64, reserve_1: u64) -> CpmmPool {
+        CpmmPool {
+            address: Pubkey::new_unique(),
+            vault_0: Pubkey::new_unique(),
+            vault_1: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            mint_0: Pubkey::new_unique(),
+            mint_1: Pubkey::new_unique(),
+            program_0: spl_token::id(),
+            program_1: spl_token::id(),
+            status: 0,
+            decimals_0: 6,
+            decimals_1: 6,
+            lp_supply,
+            reserve_0,
+            reserve_1,
+        }
+    }
+
+    #[test]
+    fn deposit_quote_rounds_against_the_user() {
+        let pool = pool(3, 100, 50);
+        // 34 of token 0 buys 1 of 3 LP tokens (1.02, rounded down); the user
+        // then pays at least their share of each reserve
+        assert_eq!(pool.deposit_quote(&pool.mint_0, 34), Ok((1, 34, 17)));
+        assert_eq!(pool.deposit_quote(&pool.mint_1, 17), Ok((1, 34, 17)));
+        // 33 isn't enough for a whole LP token
+        assert!(pool.deposit_quote(&pool.mint_0, 33).is_err());
+        assert!(pool.deposit_quote(&Pubkey::new_unique(), 1_000).is_err());
+    }
+
+    #[test]
+    fn slippage_moves_limits_by_bps() {
+        assert_eq!(with_slippage(1_000_000, 50, true), 1_005_000);
+        assert_eq!(with_slippage(1_000_000, 50, false), 995_000);
+        assert_eq!(with_slippage(1_000_000, 0, true), 1_000_000);
+        // Minimums round down, so 0.5% off 999 is 994
+        assert_eq!(with_slippage(999, 50, false), 994);
+        assert_eq!(with_slippage(999, 50, true), 1_003);
+    }
+
+    #[test]
+    fn slippage_saturates_at_u64() {
+        assert_eq!(with_slippage(u64::MAX, 1_000, true), u64::MAX);
+        assert_eq!(with_slippage(u64::MAX, 0, false), u64::MAX);
+    }
+
+    #[test]
+    fn slippage_over_100_percent_floors_a_min_at_zero() {
+        assert_eq!(with_slippage(1_000, 10_000, false), 0);
+        assert_eq!(with_slippage(1_000, u16::MAX, false), 0);
+        assert_eq!(with_slippage(1_000, 20_000, true), 3_000);
+    }
+}
@@ -1,9 +1,9 @@
 use axum::{
-    extract::{State, Json},
+    extract::{DefaultBodyLimit, State, Json, Query},
     http::StatusCode,
-    routing::post,
+    routing::{get, post},
     Router,
-    response::IntoResponse,
+    response::{IntoResponse, sse::{Event, KeepAlive, Sse}},
     middleware,
 };
 use serde::{Deserialize, Serialize};
@@ -12,11 +12,36 @@ use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
 use tower_http::cors::{Any, CorsLayer};
 use dotenv::dotenv;
 use std::env;
+use std::str::FromStr;
 
 // --- MODULES ---
 mod ai;
 mod swap;
 mod payment;
+mod rpc;
+mod nonce;
+mod submit;
+mod stream;
+mod nft;
+mod metadata;
+mod operator;
+mod cnft;
+mod stake;
+mod validators;
+mod lp;
+mod wsol;
+mod cleanup;
+mod burn;
+mod token;
+mod batch;
+mod split;
+mod schedule;
+mod trigger;
+mod airdrop;
+mod faucet;
+mod testamm;
+mod portfolio;
+mod prices;
 
 // --- SHARED STATE ---
 #[derive(Clone)]
@@ -25,6 +50,15 @@ struct AppState {
     key_index: Arc<AtomicUsize>,
     fee_wallet: String,
     fee_lamports: u64,
+    metadata_store: Arc<dyn metadata::MetadataStore>,
+    operator: Option<Arc<solana_sdk::signature::Keypair>>,
+    trees: Arc<cnft::TreeRegistry>,
+    validators: Arc<validators::ValidatorDirectory>,
+    schedules: Arc<schedule::ScheduleStore>,
+    test_mints: Arc<faucet::TestMints>,
+    test_pools: Arc<testamm::TestPools>,
+    prices: Arc<prices::PriceCache>,
+    streams: Arc<stream::StreamLimits>,
 }
 
 impl AppState {
@@ -35,7 +69,7 @@ impl AppState {
 }
 
 fn sanitize_key(key: String) -> String {
-    key.trim().replace('\r', "").replace('\n', "")
+    key.trim().replace(['\r', '\n'], "")
 }
 
 #[tokio::main]
@@ -54,21 +88,50 @@ async fn main() {
         .parse()
         .unwrap_or(5000);
 
+    let data_dir = std::path::PathBuf::from(env::var("DATA_DIR").unwrap_or("./data".to_string()));
+
     let state = AppState {
         gemini_keys: keys,
         key_index: Arc::new(AtomicUsize::new(0)),
         fee_wallet,
         fee_lamports,
+        metadata_store: metadata::store_from_env(),
+        operator: operator::load_from_env(),
+        trees: Arc::new(cnft::TreeRegistry::load(data_dir.join("cnft_trees.json"))),
+        validators: Arc::new(validators::ValidatorDirectory::from_env()),
+        schedules: Arc::new(schedule::ScheduleStore::load(data_dir.join("schedules.json"))),
+        test_mints: Arc::new(faucet::TestMints::load(data_dir.join("test_mints.json"))),
+        test_pools: Arc::new(testamm::TestPools::load(data_dir.join("test_pools.json"))),
+        prices: Arc::new(prices::PriceCache::from_env()),
+        streams: Arc::new(stream::StreamLimits::default()),
     };
 
+    tokio::spawn(run_schedules(state.clone()));
+
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_headers(Any)
         .allow_methods(Any);
 
     let app = Router::new()
-        .route("/agent/execute", post(handle_execute))
+        .route("/agent/execute", post(handle_execute).layer(DefaultBodyLimit::max(metadata::MAX_REQUEST_BYTES)))
+        .route("/agent/nonce", post(handle_nonce))
+        .route("/agent/submit", post(handle_submit))
+        .route("/agent/schedules", post(handle_create_schedule))
         .layer(middleware::from_fn(payment::x402_middleware))
+        // EventSource can't set X-Payment-Sig, so the status stream sits outside the paywall, capped per IP
+        .route("/agent/stream", get(handle_stream))
+        // Wallets and explorers fetch NFT metadata without paying
+        .route("/metadata/:file", get(serve_metadata))
+        .route("/validators", get(handle_validators))
+        .route("/faucet/mints", get(handle_faucet_mints))
+        // Schedule management needs a wallet signature (see `require_owner`); creating one also pays above
+        .route("/schedules", get(handle_list_schedules))
+        .route("/schedules/:id", get(handle_get_schedule).patch(handle_update_schedule).delete(handle_delete_schedule))
+        .route("/schedules/:id/run", post(handle_run_schedule))
+        // Admin routes carry their own X-Admin-Token check
+        .route("/admin/trees", get(handle_list_trees).post(handle_create_tree))
+        .route("/admin/trees/size", post(handle_size_tree))
         .layer(cors)
         .with_state(state);
 
@@ -76,7 +139,7 @@ async fn main() {
     let addr = format!("0.0.0.0:{}", port);
     println!("[SERVER] Backend running on {}", addr);
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
 }
 
 // --- REQUEST/RESPONSE MODELS ---
@@ -86,6 +149,27 @@ struct UserRequest {
     user_pubkey: String,
     #[serde(default = "default_network")]
     network: String,
+    /// Build on the user's durable nonce instead of a recent blockhash
+    #[serde(default)]
+    durable_nonce: bool,
+    /// Optional artwork for MINT_NFT
+    #[serde(default)]
+    image: Option<metadata::UploadedImage>,
+    /// Required for irreversible actions (BURN); without it they only preview
+    #[serde(default)]
+    confirm_irreversible: bool,
+    /// Uploaded payout list for BATCH_TRANSFER, `address[,amount]` per line
+    #[serde(default)]
+    csv: Option<String>,
+    /// Repeat the intent on this cadence instead of building it once
+    #[serde(default)]
+    schedule: Option<String>,
+    /// Webhook told when a scheduled run is ready to sign
+    #[serde(default)]
+    notify_url: Option<String>,
+    /// Caller's IP for per-client limits; unset for scheduled runs
+    #[serde(skip)]
+    client_ip: Option<std::net::IpAddr>,
 }
 
 fn default_network() -> String { "devnet".to_string() }
@@ -101,12 +185,13 @@ struct AgentResponse {
 // --- MAIN HANDLER ---
 async fn handle_execute(
     State(state): State<AppState>,
-    Json(payload): Json<UserRequest>,
+    axum::extract::ConnectInfo(peer): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    headers: axum::http::HeaderMap,
+    Json(mut payload): Json<UserRequest>,
 ) -> impl IntoResponse {
+    payload.client_ip = Some(client_ip(&headers, peer));
     println!("[REQ] prompt={} network={}", payload.prompt, payload.network);
 
-    let is_devnet = payload.network != "mainnet";
-
     // 1. AI Parsing (Gemini)
     let intent = match ai::parse_intent(&state.get_next_key(), &payload.prompt).await {
         Ok(i) => i,
@@ -115,6 +200,124 @@ async fn handle_execute(
 
     println!("[INTENT] {:?}", intent);
 
+    // A cadence from the request or the prompt registers a schedule instead
+    if let Some(cadence) = payload.schedule.clone().or(intent.schedule.clone()).filter(|c| !c.trim().is_empty()) {
+        return create_schedule(&state, &headers, intent, &payload, &cadence).await;
+    }
+
+    execute_intent(&state, intent, &payload).await
+}
+
+/// Swap backend for a network: Jupiter on mainnet, the operator's test AMM
+/// elsewhere. `None` when a test network has no operator keypair to seed pools.
+fn swap_provider(state: &AppState, network: &str) -> Option<Box<dyn swap::SwapProvider>> {
+    if network == "mainnet" {
+        return Some(Box::new(swap::Jupiter));
+    }
+    Some(Box::new(testamm::TestAmm {
+        network: network.to_string(),
+        operator: state.operator.clone()?,
+        mints: state.test_mints.clone(),
+        pools: state.test_pools.clone(),
+    }))
+}
+
+/// Build whatever `intent` asks for and value it in USD. Shared by
+/// `/agent/execute` and the schedule runner.
+async fn execute_intent(state: &AppState, intent: ai::Intent, payload: &UserRequest) -> axum::response::Response {
+    let resp = route_intent(state, intent, payload).await;
+    with_usd_value(state, resp, &payload.network).await
+}
+
+/// Mainnet mint to price a token by. Test networks price their stand-ins
+/// as the real token, so only registry symbols resolve there.
+fn price_mint(token: &str, network: &str) -> Option<String> {
+    let symbol = match token.trim().to_uppercase().as_str() {
+        "WSOL" => "SOL".to_string(),
+        s => s.to_string(),
+    };
+    match swap::token_mint(&symbol) {
+        Some(mint) => Some(mint.to_string()),
+        None if network == "mainnet" => solana_sdk::pubkey::Pubkey::from_str(token.trim()).ok().map(|_| token.trim().to_string()),
+        None => None,
+    }
+}
+
+/// Add `meta.usd` to a successful response that moves `amount` (or a batch
+/// `total`) of `token_in`
+async fn with_usd_value(state: &AppState, resp: axum::response::Response, network: &str) -> axum::response::Response {
+    if resp.status() != StatusCode::OK {
+        return resp;
+    }
+    let (mut parts, body) = resp.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json_err(format!("Failed to read response: {}", e)))).into_response(),
+    };
+    let rebuild = |parts: axum::http::response::Parts, bytes: Vec<u8>| {
+        axum::response::Response::from_parts(parts, axum::body::Body::from(bytes))
+    };
+    let mut value: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(v) => v,
+        Err(_) => return rebuild(parts, bytes.to_vec()),
+    };
+
+    let meta = &value["meta"];
+    let amount = meta["amount"].as_f64().or_else(|| meta["total"].as_f64())
+        // LP_REMOVE's amount is a percentage of the position, not tokens
+        .filter(|_| value["action_type"] != "LP_REMOVE");
+    let mint = meta["token_in"].as_str().and_then(|t| price_mint(t, network));
+    let (Some(amount), Some(mint)) = (amount, mint) else {
+        return rebuild(parts, bytes.to_vec());
+    };
+
+    if let Some(price) = state.prices.price(&mint).await {
+        value["meta"]["usd"] = json!({
+            "value": amount * price.usd,
+            "price": price.usd,
+            "source": price.source,
+            "published_at": price.published_at,
+        });
+    }
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    rebuild(parts, serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec()))
+}
+
+/// Actions whose transaction carries the intent's memo
+const MEMO_ACTIONS: &[&str] = &["SWAP", "TRANSFER", "SPLIT_PAYMENT", "TRANSFER_NFT"];
+
+async fn route_intent(state: &AppState, intent: ai::Intent, payload: &UserRequest) -> axum::response::Response {
+    let is_devnet = payload.network != "mainnet";
+
+    // ── GUARDRAIL: Validate memo up front for the actions that attach one; the rest ignore it ──
+    let memo = intent.memo.as_deref().map(str::trim).filter(|m| !m.is_empty())
+        .filter(|_| MEMO_ACTIONS.contains(&intent.action.as_str()));
+    if let Some(m) = memo {
+        if let Err(e) = swap::validate_memo(m) {
+            return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response();
+        }
+    }
+
+    // ── Durable nonce: the account must exist before anything can be built on it ──
+    let nonce = if payload.durable_nonce {
+        let rpc = rpc::client(&payload.network);
+        match nonce::fetch_nonce(&rpc, &payload.user_pubkey).await {
+            Ok(Some(info)) => Some(info),
+            Ok(None) => return match nonce::build_create_nonce_tx(&rpc, &payload.user_pubkey).await {
+                Ok(tx) => (StatusCode::OK, Json(AgentResponse {
+                    action_type: "CREATE_NONCE".to_string(),
+                    tx_base64: Some(tx),
+                    meta: Some(json!({ "action": "Set up durable nonce", "network": payload.network, "fee": "~0.0015 SOL (refundable rent)" })),
+                    message: "Durable signing needs a nonce account first. Sign this once, then resend your command.".to_string(),
+                })).into_response(),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            },
+            Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+        }
+    } else {
+        None
+    };
+
     // 2. Action Routing
     match intent.action.as_str() {
         "SWAP" => {
@@ -130,34 +333,243 @@ async fn handle_execute(
                 ))).into_response();
             }
 
-            // ── Devnet: Mock swap (self-transfer) ──
-            if is_devnet {
-                match swap::build_mock_swap_tx(&payload.user_pubkey) {
-                    Ok(tx) => return (StatusCode::OK, Json(AgentResponse {
-                        action_type: "SWAP".to_string(),
-                        tx_base64: Some(tx),
-                        meta: Some(json!({ "action": "Swap", "amount": intent.amount, "token_in": intent.token_in, "token_out": intent.token_out, "recipient": null, "network": payload.network, "fee": "~0.000005 SOL" })),
-                        message: format!("Devnet Mock: Swap {} {} -> {} (self-transfer)", intent.amount, intent.token_in, intent.token_out),
-                    })).into_response(),
-                    Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
-                }
+            let slippage_bps = intent.slippage_bps.unwrap_or(swap::DEFAULT_SLIPPAGE_BPS);
+            if slippage_bps > swap::MAX_SLIPPAGE_BPS {
+                return (StatusCode::BAD_REQUEST, Json(json_err(format!("Slippage above {}% is not allowed", swap::MAX_SLIPPAGE_BPS / 100)))).into_response();
             }
 
-            // ── Mainnet: Real Jupiter swap ──
-            match swap::get_jupiter_swap(&intent.token_in, &intent.token_out, intent.amount, &payload.user_pubkey).await {
-                Ok(tx) => {
+            let provider = match swap_provider(state, &payload.network) {
+                Some(p) => p,
+                None => return (StatusCode::SERVICE_UNAVAILABLE, Json(json_err(
+                    format!("Swaps on {} are not configured on this server", payload.network)
+                ))).into_response(),
+            };
+
+            match provider.build_swap(&intent.token_in, &intent.token_out, intent.amount, &payload.user_pubkey, slippage_bps).await {
+                Ok(plan) => {
+                    // Attach memo before the fee so it sits next to the swap instructions
+                    let tx = match memo {
+                        Some(m) => match swap::append_memo_to_tx(&plan.tx_base64, &payload.user_pubkey, m) {
+                            Ok(t) => t,
+                            Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                        },
+                        None => plan.tx_base64.clone(),
+                    };
+
                     // Append fee if configured
                     let final_tx = swap::append_fee_to_tx(
                         &tx, &payload.user_pubkey, &state.fee_wallet, state.fee_lamports
                     ).unwrap_or(tx);
 
-                    (StatusCode::OK, Json(AgentResponse {
+                    let message = if is_devnet {
+                        format!("Swapping {} test {} to ~{} test {} on the {} test pool", intent.amount, intent.token_in, plan.expected_out, intent.token_out, payload.network)
+                    } else {
+                        format!("Swapping {} {} to {}", intent.amount, intent.token_in, intent.token_out)
+                    };
+                    respond(nonce.as_ref(), AgentResponse {
                         action_type: "SWAP".to_string(),
                         tx_base64: Some(final_tx),
-                        meta: Some(json!({ "action": "Swap", "amount": intent.amount, "token_in": intent.token_in, "token_out": intent.token_out, "recipient": null, "network": payload.network, "fee": "~0.000005 SOL" })),
-                        message: format!("Swapping {} {} to {}", intent.amount, intent.token_in, intent.token_out),
+                        meta: Some(json!({ "action": "Swap", "amount": intent.amount, "token_in": intent.token_in, "token_out": intent.token_out, "recipient": null, "network": payload.network, "fee": "~0.000005 SOL", "memo": memo, "quote": plan })),
+                        message,
+                    })
+                },
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "LIMIT_ORDER" | "LIST_ORDERS" | "CANCEL_ORDER" => {
+            // Jupiter's trigger program only runs on mainnet
+            if is_devnet {
+                return (StatusCode::BAD_REQUEST, Json(json_err(
+                    format!("Limit orders run through Jupiter on mainnet only, not {}", payload.network)
+                ))).into_response();
+            }
+            let token_in = intent.token_in.trim().to_uppercase();
+            let token_out = intent.token_out.trim().to_uppercase();
+
+            match intent.action.as_str() {
+                "LIMIT_ORDER" => {
+                    let price = intent.limit_price.unwrap_or(0.0);
+                    match trigger::create_order(&token_in, &token_out, intent.amount, price, &payload.user_pubkey).await {
+                        Ok(plan) => {
+                            let receive = swap::from_atomic(&token_out, plan.taking_amount);
+                            let message = format!("Limit order: {} {} for {} {} (price {})", intent.amount, token_in, receive, token_out, price);
+                            respond(nonce.as_ref(), AgentResponse {
+                                action_type: "LIMIT_ORDER".to_string(),
+                                tx_base64: Some(plan.tx_base64.clone()),
+                                meta: Some(json!({
+                                    "action": "Limit Order",
+                                    "amount": intent.amount,
+                                    "token_in": token_in,
+                                    "token_out": token_out,
+                                    "limit_price": price,
+                                    "receive": receive,
+                                    "order": plan,
+                                    "network": payload.network,
+                                    "fee": "~0.000005 SOL + order account rent",
+                                })),
+                                message,
+                            })
+                        },
+                        Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                    }
+                },
+                "LIST_ORDERS" => match trigger::list_orders(&payload.user_pubkey).await {
+                    Ok(orders) => (StatusCode::OK, Json(AgentResponse {
+                        action_type: "LIST_ORDERS".to_string(),
+                        tx_base64: None,
+                        message: format!("{} open limit order(s)", orders.len()),
+                        meta: Some(json!({ "orders": orders, "network": payload.network })),
+                    })).into_response(),
+                    Err(e) => (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+                },
+                _ => {
+                    let order = intent.order.as_deref().map(str::trim).filter(|o| !o.is_empty());
+                    match trigger::cancel_orders(&payload.user_pubkey, order).await {
+                        Ok(txs) if txs.is_empty() => (StatusCode::OK, Json(AgentResponse {
+                            action_type: "CANCEL_ORDER".to_string(),
+                            tx_base64: None,
+                            meta: Some(json!({ "action": "Cancel Orders", "network": payload.network })),
+                            message: "No open limit orders to cancel".to_string(),
+                        })).into_response(),
+                        Ok(txs) => {
+                            let message = match order {
+                                Some(o) => format!("Cancelling limit order {}", o),
+                                None => format!("Cancelling all open limit orders ({} transaction(s))", txs.len()),
+                            };
+                            respond_batch(nonce.as_ref(), "CANCEL_ORDER", txs, json!({
+                                "action": "Cancel Orders",
+                                "order": order,
+                                "network": payload.network,
+                                "fee": "~0.000005 SOL per transaction",
+                            }), message)
+                        },
+                        Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                    }
+                },
+            }
+        },
+        "AIRDROP" => {
+            let user = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+            let amount = if intent.amount > 0.0 { intent.amount } else { airdrop::DEFAULT_AIRDROP_SOL };
+
+            let rpc = rpc::client(&payload.network);
+            match airdrop::request_airdrop(&rpc, &payload.network, &user, amount).await {
+                Ok(drop) => {
+                    let balance = drop.balance as f64 / 1_000_000_000.0;
+                    (StatusCode::OK, Json(AgentResponse {
+                        action_type: "AIRDROP".to_string(),
+                        tx_base64: None,
+                        meta: Some(json!({
+                            "action": "Airdrop",
+                            "amount": drop.lamports as f64 / 1_000_000_000.0,
+                            "token_in": "SOL",
+                            "signature": drop.signature.to_string(),
+                            "balance_lamports": drop.balance,
+                            "balance_sol": balance,
+                            "network": payload.network,
+                        })),
+                        message: format!("Airdropped {} SOL. New balance: {:.4} SOL", amount, balance),
                     })).into_response()
                 },
+                Err(airdrop::AirdropError::RateLimited(e)) => (StatusCode::TOO_MANY_REQUESTS, Json(json_err(e))).into_response(),
+                Err(airdrop::AirdropError::Failed(e)) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "QUERY_BALANCE" => {
+            let rpc = rpc::client(&payload.network);
+            let mut portfolio = match portfolio::fetch_portfolio(&rpc, &payload.user_pubkey, |mint| {
+                match swap::token_symbol(mint) {
+                    Some(s) if !is_devnet => Some(s.to_string()),
+                    // Registry mints don't exist off mainnet; the faucet's stand-ins do
+                    _ => state.test_mints.symbol_for(&payload.network, mint),
+                }
+            }).await {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+            };
+
+            // Mainnet holdings price by their own mint; test-network ones by the token they stand in for
+            let holding_mint = |h: &portfolio::Holding| if is_devnet {
+                h.symbol.as_deref().and_then(|s| price_mint(s, &payload.network))
+            } else {
+                Some(h.mint.clone())
+            };
+            let sol_mint = wsol::wsol_mint().to_string();
+            let mut mints: Vec<String> = portfolio.tokens.iter().filter_map(holding_mint).collect();
+            mints.push(sol_mint.clone());
+            let prices = state.prices.prices(&mints).await;
+            portfolio.value(
+                prices.get(&sol_mint).map(|p| p.usd),
+                |h| holding_mint(h).and_then(|m| prices.get(&m)).map(|p| p.usd),
+            );
+
+            let token = intent.token_in.trim().to_uppercase();
+            let worth = portfolio.total_usd.map(|t| format!(" (~${:.2})", t)).unwrap_or_default();
+            let message = match token.as_str() {
+                "" => format!(
+                    "You have {:.4} SOL, {} other token(s) and {} NFT(s){}",
+                    portfolio.sol, portfolio.tokens.len(), portfolio.nfts.len(), worth
+                ),
+                "SOL" => format!("You have {:.4} SOL{}", portfolio.sol, portfolio.sol_usd_value.map(|v| format!(" (~${:.2})", v)).unwrap_or_default()),
+                _ => match portfolio.find(&token) {
+                    Some(h) => format!("You have {} {}{}", h.amount, token, h.usd_value.map(|v| format!(" (~${:.2})", v)).unwrap_or_default()),
+                    None => format!("You have no {}", token),
+                },
+            };
+            (StatusCode::OK, Json(AgentResponse {
+                action_type: "QUERY_BALANCE".to_string(),
+                tx_base64: None,
+                meta: Some(json!({
+                    "action": "Balance",
+                    "token_in": if token.is_empty() { None } else { Some(&token) },
+                    "portfolio": portfolio,
+                    "network": payload.network,
+                })),
+                message,
+            })).into_response()
+        },
+        "FAUCET" => {
+            let operator = match &state.operator {
+                Some(k) => k.clone(),
+                None => return (StatusCode::SERVICE_UNAVAILABLE, Json(json_err("The test-token faucet is not configured on this server".into()))).into_response(),
+            };
+            let user = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+            let token = if intent.token_out.trim().is_empty() { &intent.token_in } else { &intent.token_out }.trim().to_uppercase();
+            let amount = if intent.amount > 0.0 { intent.amount } else { faucet::DEFAULT_FAUCET_AMOUNT };
+
+            let rpc = rpc::client(&payload.network);
+            let mint = match state.test_mints.prepare(&rpc, &operator, &payload.network, &user, payload.client_ip, &token).await {
+                Ok(m) => m,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+            match faucet::build_faucet_tx(&rpc, &operator, &mint, &user, &token, amount, nonce.as_ref()).await {
+                Ok(drip) => {
+                    state.test_mints.record_drip(&payload.network, &user, payload.client_ip, &token);
+                    respond(nonce.as_ref(), AgentResponse {
+                        action_type: "FAUCET".to_string(),
+                        tx_base64: Some(drip.tx_base64),
+                        meta: Some(json!({
+                            "action": format!("Get test {}", token),
+                            "amount": amount,
+                            "amount_atomic": drip.amount_atomic,
+                            "decimals": drip.decimals,
+                            "token_out": token,
+                            "mint": drip.mint.to_string(),
+                            "token_account": drip.token_account.to_string(),
+                            "network": payload.network,
+                            "fee": "~0.000005 SOL (+ ~0.002 SOL rent for a new token account)",
+                            // Partially signed by the mint authority - the blockhash is final
+                            "keep_blockhash": true,
+                        })),
+                        message: format!("Minting {} test {} to your wallet", amount, token),
+                    })
+                },
                 Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
             }
         },
@@ -171,13 +583,13 @@ async fn handle_execute(
 
             // Native SOL transfer
             if token == "SOL" || token.is_empty() {
-                match swap::build_transfer_sol(&payload.user_pubkey, &recipient, intent.amount) {
-                    Ok(tx) => return (StatusCode::OK, Json(AgentResponse {
+                match swap::build_transfer_sol(&payload.user_pubkey, &recipient, intent.amount, memo) {
+                    Ok(tx) => return respond(nonce.as_ref(), AgentResponse {
                         action_type: "TRANSFER".to_string(),
                         tx_base64: Some(tx),
-                        meta: Some(json!({ "action": "Send SOL", "amount": intent.amount, "token_in": "SOL", "token_out": null, "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL" })),
+                        meta: Some(json!({ "action": "Send SOL", "amount": intent.amount, "token_in": "SOL", "token_out": null, "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL", "memo": memo })),
                         message: format!("Sending {} SOL to {}...{}", intent.amount, &recipient[..4.min(recipient.len())], &recipient[recipient.len().saturating_sub(4)..]),
-                    })).into_response(),
+                    }),
                     Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
                 }
             }
@@ -190,15 +602,28 @@ async fn handle_execute(
                 ))).into_response(),
             };
 
-            // On devnet, mainnet mints don't exist - use mock
+            // On devnet, mainnet mints don't exist - move the faucet's test
+            // token if there is one, otherwise fall back to a mock
+            if let Some(test_mint) = is_devnet.then(|| state.test_mints.get(&payload.network, &token)).flatten() {
+                let amount_atomic = swap::to_atomic(&token, intent.amount);
+                match swap::build_transfer_spl(&payload.user_pubkey, &recipient, &test_mint.to_string(), amount_atomic, memo) {
+                    Ok(tx) => return respond(nonce.as_ref(), AgentResponse {
+                        action_type: "TRANSFER".to_string(),
+                        tx_base64: Some(tx),
+                        meta: Some(json!({ "action": format!("Send {}", token), "amount": intent.amount, "token_in": token, "token_out": null, "mint": test_mint.to_string(), "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL", "memo": memo })),
+                        message: format!("Sending {} test {} to {}...{}", intent.amount, token, &recipient[..4.min(recipient.len())], &recipient[recipient.len().saturating_sub(4)..]),
+                    }),
+                    Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                }
+            }
             if is_devnet {
-                match swap::build_transfer_sol(&payload.user_pubkey, &payload.user_pubkey, 0.000001) {
-                    Ok(tx) => return (StatusCode::OK, Json(AgentResponse {
+                match swap::build_transfer_sol(&payload.user_pubkey, &payload.user_pubkey, 0.000001, memo) {
+                    Ok(tx) => return respond(nonce.as_ref(), AgentResponse {
                         action_type: "TRANSFER".to_string(),
                         tx_base64: Some(tx),
-                        meta: Some(json!({ "action": format!("Send {}", token), "amount": intent.amount, "token_in": token, "token_out": null, "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL" })),
-                        message: format!("Devnet Mock: {} {} transfer to {}...{}", intent.amount, token, &recipient[..4.min(recipient.len())], &recipient[recipient.len().saturating_sub(4)..]),
-                    })).into_response(),
+                        meta: Some(json!({ "action": format!("Send {}", token), "amount": intent.amount, "token_in": token, "token_out": null, "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL", "memo": memo })),
+                        message: format!("Devnet Mock: {} {} transfer to {}...{}. Ask the faucet for test {} to send real tokens", intent.amount, token, &recipient[..4.min(recipient.len())], &recipient[recipient.len().saturating_sub(4)..], token),
+                    }),
                     Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
                 }
             }
@@ -212,32 +637,1347 @@ async fn handle_execute(
                 &recipient,
                 mint_address,
                 amount_atomic,
+                memo,
             ) {
-                Ok(tx) => (StatusCode::OK, Json(AgentResponse {
+                Ok(tx) => respond(nonce.as_ref(), AgentResponse {
                     action_type: "TRANSFER".to_string(),
                     tx_base64: Some(tx),
-                    meta: Some(json!({ "action": format!("Send {}", token), "amount": intent.amount, "token_in": token, "token_out": null, "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL" })),
+                    meta: Some(json!({ "action": format!("Send {}", token), "amount": intent.amount, "token_in": token, "token_out": null, "recipient": recipient, "network": payload.network, "fee": "~0.000005 SOL", "memo": memo })),
                     message: format!("Sending {} {} to {}...{}", intent.amount, token, &recipient[..4.min(recipient.len())], &recipient[recipient.len().saturating_sub(4)..]),
-                })).into_response(),
+                }),
                 Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
             }
         },
+        "SPLIT_PAYMENT" => {
+            let token = if intent.token_in.trim().is_empty() { "SOL".to_string() } else { intent.token_in.trim().to_uppercase() };
+            // On devnet, mainnet mints don't exist
+            if is_devnet && token != "SOL" {
+                return (StatusCode::BAD_REQUEST, Json(json_err(
+                    format!("{} doesn't exist on {}; split SOL instead", token, payload.network)
+                ))).into_response();
+            }
+
+            match split::build_split_tx(&payload.user_pubkey, &token, intent.amount, &intent.recipients, &intent.weights, memo) {
+                Ok(plan) => respond(nonce.as_ref(), AgentResponse {
+                    action_type: "SPLIT_PAYMENT".to_string(),
+                    tx_base64: Some(plan.tx_base64),
+                    meta: Some(json!({
+                        "action": format!("Split {}", token),
+                        "amount": intent.amount,
+                        "token_in": token,
+                        "total_atomic": plan.total_atomic,
+                        "decimals": plan.decimals,
+                        "shares": plan.shares,
+                        "network": payload.network,
+                        "fee": "~0.000005 SOL",
+                        "memo": memo,
+                    })),
+                    message: format!("Splitting {} {} between {} recipients", intent.amount, token, plan.shares.len()),
+                }),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "TRANSFER_NFT" => {
+            let recipient = match intent.recipient.as_deref().map(solana_sdk::pubkey::Pubkey::from_str) {
+                Some(Ok(r)) => r,
+                Some(Err(e)) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid recipient pubkey: {}", e)))).into_response(),
+                None => return (StatusCode::BAD_REQUEST, Json(json_err("Missing recipient address".into()))).into_response(),
+            };
+            let owner = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+
+            let rpc = rpc::client(&payload.network);
+            let query = intent.nft_name.clone().unwrap_or_default();
+            let owned = match nft::find_owned_nft(&rpc, &owner, &query).await {
+                Ok(n) => n,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+
+            let standard = if owned.is_programmable() { "programmable" } else { "legacy" };
+            let recipient_str = recipient.to_string();
+            match nft::build_transfer_nft_tx(&owner, &recipient, &owned, memo) {
+                Ok(tx) => respond(nonce.as_ref(), AgentResponse {
+                    action_type: "TRANSFER_NFT".to_string(),
+                    tx_base64: Some(tx),
+                    meta: Some(json!({
+                        "action": "Send NFT",
+                        "name": owned.name,
+                        "mint": owned.mint.to_string(),
+                        "token_account": owned.token_account.to_string(),
+                        "standard": standard,
+                        "recipient": recipient_str,
+                        "network": payload.network,
+                        "fee": "~0.000005 SOL",
+                        "memo": memo,
+                    })),
+                    message: format!("Sending \"{}\" to {}...{}", owned.name, &recipient_str[..4], &recipient_str[recipient_str.len() - 4..]),
+                }),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "STAKE" => {
+            // No validator given means "pick a good one"
+            let rpc = rpc::client(&payload.network);
+            let query = intent.validator.as_deref().unwrap_or("best");
+            let chosen = match state.validators.resolve(&rpc, &payload.network, query).await {
+                Ok(v) => v,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+            let validator = chosen.vote_account.clone();
+
+            let info = match stake::validate_vote_account(&rpc, &validator).await {
+                Ok(i) => i,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+
+            match stake::build_stake_tx(&rpc, &payload.user_pubkey, &info, intent.amount).await {
+                Ok(staked) => respond(nonce.as_ref(), AgentResponse {
+                    action_type: "STAKE".to_string(),
+                    tx_base64: Some(staked.tx_base64),
+                    meta: Some(json!({
+                        "action": "Stake SOL",
+                        "amount": intent.amount,
+                        "token_in": "SOL",
+                        "stake_account": staked.stake_account.to_string(),
+                        "seed": staked.seed,
+                        "lamports": staked.lamports,
+                        "rent_lamports": staked.rent_lamports,
+                        "vote_account": validator,
+                        "validator_name": chosen.name,
+                        "validator_score": chosen.score,
+                        "validator_identity": info.identity,
+                        "commission": info.commission,
+                        "activated_stake": info.activated_stake,
+                        "activation_epoch": staked.activation_epoch,
+                        "active_from_epoch": staked.activation_epoch + 1,
+                        "epoch_ends_in_secs": staked.epoch_ends_in_secs,
+                        "network": payload.network,
+                        "fee": "~0.000005 SOL",
+                    })),
+                    message: format!(
+                        "Staking {} SOL with {} ({}% commission), active from epoch {}",
+                        intent.amount,
+                        chosen.name.clone().unwrap_or_else(|| format!("{}...{}", &validator[..4], &validator[validator.len() - 4..])),
+                        info.commission,
+                        staked.activation_epoch + 1
+                    ),
+                }),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "LIST_STAKES" | "UNSTAKE" | "WITHDRAW_STAKE" | "MERGE_STAKE" | "SPLIT_STAKE" => {
+            manage_stake(&intent, payload, nonce.as_ref()).await
+        },
+        "LP" | "LP_REMOVE" => {
+            let user = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+            let (mint_a, mint_b) = match (swap::token_mint(&intent.token_in), swap::token_mint(&intent.token_out)) {
+                (Some(a), Some(b)) if a != b => (
+                    solana_sdk::pubkey::Pubkey::from_str(a).unwrap(),
+                    solana_sdk::pubkey::Pubkey::from_str(b).unwrap(),
+                ),
+                _ => return (StatusCode::BAD_REQUEST, Json(json_err(
+                    format!("Unknown pair '{}/{}'. Supported: SOL, USDC, USDT, BONK, JUP, RAY, WIF", intent.token_in, intent.token_out)
+                ))).into_response(),
+            };
+            let slippage_bps = intent.slippage_bps.unwrap_or(swap::DEFAULT_SLIPPAGE_BPS);
+            if slippage_bps > swap::MAX_SLIPPAGE_BPS {
+                return (StatusCode::BAD_REQUEST, Json(json_err(format!("Slippage above {}% is not allowed", swap::MAX_SLIPPAGE_BPS / 100)))).into_response();
+            }
+
+            let rpc = rpc::client(&payload.network);
+            let adapter = lp::adapter_for(&payload.network);
+            let pool = match intent.pool.as_deref().map(solana_sdk::pubkey::Pubkey::from_str) {
+                Some(Ok(p)) => p,
+                Some(Err(e)) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid pool address: {}", e)))).into_response(),
+                None => match adapter.find_pool(&rpc, &mint_a, &mint_b).await {
+                    Ok(p) => p,
+                    Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                },
+            };
+
+            let removing = intent.action == "LP_REMOVE";
+            let planned = if removing {
+                // amount is the share of the position; 0 means all of it
+                let percent = if intent.amount > 0.0 { intent.amount } else { 100.0 };
+                adapter.remove_liquidity(&rpc, &user, &pool, percent, slippage_bps).await
+            } else {
+                let amount_in = (intent.amount * 10f64.powi(swap::token_decimals(&intent.token_in) as i32)) as u64;
+                adapter.add_liquidity(&rpc, &user, &pool, &mint_a, amount_in, slippage_bps).await
+            };
+
+            match planned {
+                Ok(plan) => {
+                    let message = if removing {
+                        format!("Removing liquidity from {}/{} ({})", intent.token_in, intent.token_out, plan.pool.amm)
+                    } else {
+                        format!("Adding {} {} + matching {} to {} pool", intent.amount, intent.token_in, intent.token_out, plan.pool.amm)
+                    };
+                    respond(nonce.as_ref(), AgentResponse {
+                        action_type: intent.action.clone(),
+                        tx_base64: Some(plan.tx_base64.clone()),
+                        meta: Some(json!({
+                            "action": if removing { "Remove liquidity" } else { "Add liquidity" },
+                            "amount": intent.amount,
+                            "token_in": intent.token_in,
+                            "token_out": intent.token_out,
+                            "lp": plan,
+                            "network": payload.network,
+                            "fee": "~0.000005 SOL",
+                        })),
+                        message,
+                    })
+                },
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "WRAP" => {
+            match wsol::build_wrap_tx(&payload.user_pubkey, intent.amount) {
+                Ok(tx) => respond(nonce.as_ref(), AgentResponse {
+                    action_type: "WRAP".to_string(),
+                    tx_base64: Some(tx),
+                    meta: Some(json!({ "action": "Wrap SOL", "amount": intent.amount, "token_in": "SOL", "token_out": "wSOL", "mint": wsol::wsol_mint().to_string(), "network": payload.network, "fee": "~0.000005 SOL" })),
+                    message: format!("Wrapping {} SOL into wSOL", intent.amount),
+                }),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "UNWRAP" => {
+            let rpc = rpc::client(&payload.network);
+            match wsol::build_unwrap_tx(&rpc, &payload.user_pubkey).await {
+                Ok((tx, lamports)) => {
+                    let amount = lamports as f64 / 1_000_000_000.0;
+                    respond(nonce.as_ref(), AgentResponse {
+                        action_type: "UNWRAP".to_string(),
+                        tx_base64: Some(tx),
+                        // Closing the account always unwraps the full balance
+                        meta: Some(json!({ "action": "Unwrap SOL", "amount": amount, "token_in": "wSOL", "token_out": "SOL", "mint": wsol::wsol_mint().to_string(), "network": payload.network, "fee": "~0.000005 SOL" })),
+                        message: format!("Unwrapping {} wSOL back to SOL", amount),
+                    })
+                },
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "BATCH_TRANSFER" => {
+            let sender = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+            let mut listed = intent.recipients.clone();
+            listed.extend(intent.recipient.clone().filter(|r| !r.trim().is_empty()));
+            let list = match batch::parse_recipients(&listed, payload.csv.as_deref(), intent.amount) {
+                Ok(l) => l,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+            let token = if intent.token_in.trim().is_empty() { "SOL".to_string() } else { intent.token_in.trim().to_uppercase() };
+
+            let rpc = rpc::client(&payload.network);
+            let plan = match batch::build_batch_transfer(&rpc, &sender, &token, &list.payouts).await {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+            let total = plan.total_atomic as f64 / 10f64.powi(plan.decimals as i32);
+            let batches = plan.transactions.len();
+            respond_batch(nonce.as_ref(), "BATCH_TRANSFER", plan.transactions, json!({
+                "action": "Batch Transfer",
+                "token_in": token,
+                "mint": plan.mint.map(|m| m.to_string()),
+                "recipients": plan.manifest.len(),
+                "manifest": plan.manifest,
+                "duplicates_removed": list.duplicates,
+                "total": total,
+                "total_atomic": plan.total_atomic,
+                "network": payload.network,
+                "fee": format!("~{:.6} SOL + rent for new token accounts", batches as f64 * 0.000005),
+            }), format!("Sending {} {} to {} recipient(s) in {} transaction(s)", total, token, list.payouts.len(), batches))
+        },
+        "CLOSE_EMPTY" => {
+            let rpc = rpc::client(&payload.network);
+            let plan = match cleanup::build_cleanup(&rpc, &payload.user_pubkey).await {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+            };
+            let reclaimed = plan.total_lamports as f64 / 1_000_000_000.0;
+
+            if plan.transactions.is_empty() {
+                return (StatusCode::OK, Json(AgentResponse {
+                    action_type: "CLOSE_EMPTY".to_string(),
+                    tx_base64: None,
+                    meta: Some(json!({ "action": "Close empty token accounts", "closed": 0, "skipped": plan.skipped, "network": payload.network })),
+                    message: "No empty token accounts to close".to_string(),
+                })).into_response();
+            }
+
+            let batches = plan.transactions.len();
+            respond_batch(nonce.as_ref(), "CLOSE_EMPTY", plan.transactions, json!({
+                "action": "Close empty token accounts",
+                "accounts": plan.accounts,
+                "closed": plan.accounts.len(),
+                "skipped": plan.skipped,
+                "reclaimed_lamports": plan.total_lamports,
+                "reclaimed_sol": reclaimed,
+                "network": payload.network,
+                "fee": format!("~{:.6} SOL", batches as f64 * 0.000005),
+            }), format!("Closing {} empty token account(s) to reclaim {:.6} SOL ({} transaction(s))", plan.accounts.len(), reclaimed, batches))
+        },
+        "BURN" => {
+            let owner = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+            let rpc = rpc::client(&payload.network);
+
+            // An NFT by name/mint, or a fungible token by symbol/mint
+            let (mint, label, amount, close) = match intent.nft_name.as_deref().filter(|n| !n.trim().is_empty()) {
+                Some(query) => {
+                    let owned = match nft::find_owned_nft(&rpc, &owner, query).await {
+                        Ok(n) => n,
+                        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                    };
+                    if owned.is_programmable() {
+                        return (StatusCode::BAD_REQUEST, Json(json_err(
+                            format!("\"{}\" is a programmable NFT; it is frozen and can only be burned through Token Metadata", owned.name)
+                        ))).into_response();
+                    }
+                    (owned.mint, owned.name, None, intent.close_account.unwrap_or(true))
+                },
+                None => {
+                    let token = intent.token_in.to_uppercase();
+                    let mint = match token::resolve_mint(&intent.token_in) {
+                        Ok(m) => m,
+                        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+                    };
+                    // amount 0 burns the whole balance
+                    let amount = (intent.amount > 0.0).then_some(intent.amount);
+                    (mint, token, amount, intent.close_account.unwrap_or(false))
+                },
+            };
+
+            let plan = match burn::build_burn_tx(&rpc, &owner, &mint, amount, close).await {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            };
+            let burned = plan.amount_atomic as f64 / 10f64.powi(plan.decimals as i32);
+            let meta = json!({
+                "action": "Burn",
+                "amount": burned,
+                "token_in": label,
+                "mint": mint.to_string(),
+                "token_account": plan.token_account.to_string(),
+                "token_program": plan.program.to_string(),
+                "amount_atomic": plan.amount_atomic,
+                "decimals": plan.decimals,
+                "remaining_atomic": plan.remaining,
+                "closes_account": plan.closes_account,
+                "network": payload.network,
+                "fee": "~0.000005 SOL",
+                "irreversible": true,
+            });
+
+            // Burns can't be undone: preview until the client explicitly confirms
+            if !payload.confirm_irreversible {
+                return (StatusCode::PRECONDITION_REQUIRED, Json(AgentResponse {
+                    action_type: "CONFIRM_REQUIRED".to_string(),
+                    tx_base64: None,
+                    meta: Some(meta),
+                    message: format!("Burning {} {} is permanent. Resend with confirm_irreversible: true to continue.", burned, label),
+                })).into_response();
+            }
+
+            respond(nonce.as_ref(), AgentResponse {
+                action_type: "BURN".to_string(),
+                tx_base64: Some(plan.tx_base64),
+                meta: Some(meta),
+                message: format!("Burning {} {}{}", burned, label, if plan.closes_account { " and closing the account" } else { "" }),
+            })
+        },
+        "CREATE_TOKEN" => {
+            let user = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+            let name = intent.nft_name.clone().unwrap_or_default().trim().to_string();
+            let symbol = intent.nft_symbol.clone().unwrap_or_default().trim().to_uppercase();
+            let decimals = intent.decimals.unwrap_or(token::MAX_DECIMALS);
+
+            let rpc = rpc::client(&payload.network);
+            match token::build_create_token_tx(&rpc, &user, &name, &symbol, decimals, intent.amount, nonce.as_ref()).await {
+                Ok(created) => respond(nonce.as_ref(), AgentResponse {
+                    action_type: "CREATE_TOKEN".to_string(),
+                    tx_base64: Some(created.tx_base64),
+                    meta: Some(json!({
+                        "action": "Create Token",
+                        "name": name,
+                        "symbol": symbol,
+                        "decimals": decimals,
+                        "supply": intent.amount,
+                        "supply_atomic": created.supply_atomic,
+                        "mint": created.mint.to_string(),
+                        "token_account": created.token_account.to_string(),
+                        "metadata": created.metadata.to_string(),
+                        "mint_authority": user.to_string(),
+                        "freeze_authority": user.to_string(),
+                        "network": payload.network,
+                        "fee": "~0.0035 SOL (rent + fees)",
+                        // The mint keypair already signed; a fresh blockhash would void it
+                        "keep_blockhash": true,
+                    })),
+                    message: format!("Creating {} ({}) with {} decimals and a supply of {}", name, symbol, decimals, intent.amount),
+                }),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "MINT_MORE" | "SET_AUTHORITY" | "REVOKE_AUTHORITY" | "FREEZE" | "THAW" => {
+            manage_token(&intent, payload, nonce.as_ref()).await
+        },
         "MINT_NFT" => {
-            (StatusCode::OK, Json(AgentResponse {
-                action_type: "MINT_NFT".to_string(),
-                tx_base64: None,
-                meta: Some(serde_json::json!({
-                    "name": intent.nft_name.unwrap_or("AI Gen".to_string()),
-                    "symbol": "AI",
-                    "uri": "https://arweave.net/placeholder"
-                })),
-                message: "Minting NFT...".to_string(),
-            })).into_response()
+            let (name, symbol, published) = match publish_nft_metadata(state, &intent, payload).await {
+                Ok(p) => p,
+                Err(resp) => return resp,
+            };
+            let uri = published.uri.clone();
+
+            let rpc = rpc::client(&payload.network);
+            match nft::build_mint_nft_tx(&rpc, &payload.user_pubkey, &name, &symbol, &uri, nonce.as_ref()).await {
+                Ok(minted) => respond(nonce.as_ref(), AgentResponse {
+                    action_type: "MINT_NFT".to_string(),
+                    tx_base64: Some(minted.tx_base64),
+                    meta: Some(json!({
+                        "action": "Mint NFT",
+                        "name": name,
+                        "symbol": symbol,
+                        "uri": uri,
+                        "content_hash": published.content_hash,
+                        "image_uri": published.image_uri,
+                        "image_hash": published.image_hash,
+                        "metadata_store": published.store,
+                        "mint": minted.mint.to_string(),
+                        "token_account": minted.token_account.to_string(),
+                        "metadata": minted.metadata.to_string(),
+                        "master_edition": minted.master_edition.to_string(),
+                        "network": payload.network,
+                        "fee": "~0.02 SOL (rent)",
+                        // Partially signed by the mint keypair - the blockhash is final
+                        "keep_blockhash": true,
+                    })),
+                    message: format!("Minting NFT \"{}\"", name),
+                }),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        "MINT_CNFT" => {
+            let operator = match &state.operator {
+                Some(k) => k.clone(),
+                None => return (StatusCode::SERVICE_UNAVAILABLE, Json(json_err("Compressed minting is not configured on this server".into()))).into_response(),
+            };
+            let user_pub = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+                Ok(p) => p,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+            };
+
+            let rpc = rpc::client(&payload.network);
+            let (tree_record, tree) = match state.trees.pick_tree(&rpc, &payload.network).await {
+                Ok(t) => t,
+                Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, Json(json_err(e))).into_response(),
+            };
+
+            let (name, symbol, published) = match publish_nft_metadata(state, &intent, payload).await {
+                Ok(p) => p,
+                Err(resp) => return resp,
+            };
+
+            let args = cnft::cnft_metadata(&user_pub, &name, &symbol, &published.uri);
+            match cnft::build_mint_cnft_tx(&rpc, &operator, &tree, &user_pub, args, nonce.as_ref()).await {
+                Ok(minted) => {
+                    let cost_lamports = tree_record.cost_per_mint_lamports();
+                    respond(nonce.as_ref(), AgentResponse {
+                        action_type: "MINT_CNFT".to_string(),
+                        tx_base64: Some(minted.tx_base64),
+                        meta: Some(json!({
+                            "action": "Mint compressed NFT",
+                            "name": name,
+                            "symbol": symbol,
+                            "uri": published.uri,
+                            "content_hash": published.content_hash,
+                            "image_uri": published.image_uri,
+                            "metadata_store": published.store,
+                            "tree": minted.tree.to_string(),
+                            "leaf_index": minted.leaf_index,
+                            "asset_id": minted.asset_id.to_string(),
+                            "cost_per_mint_lamports": cost_lamports,
+                            "network": payload.network,
+                            "fee": format!("~{:.6} SOL", cost_lamports / 1_000_000_000.0),
+                            // Partially signed by the tree authority - the blockhash is final
+                            "keep_blockhash": true,
+                        })),
+                        message: format!("Minting compressed NFT \"{}\"", name),
+                    })
+                },
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
         },
         _ => (StatusCode::BAD_REQUEST, Json(json_err("Unknown Action".into()))).into_response()
     }
 }
 
+/// Stake account follow-ups: list, deactivate, withdraw, merge, split.
+/// Every response carries the user's stake accounts with their state.
+async fn manage_stake(
+    intent: &ai::Intent,
+    payload: &UserRequest,
+    nonce: Option<&nonce::NonceInfo>,
+) -> axum::response::Response {
+    use stake::StakeStatus::*;
+
+    let user = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+        Ok(p) => p,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+    };
+    let rpc = rpc::client(&payload.network);
+    let accounts = match stake::list_stake_accounts(&rpc, &user).await {
+        Ok(a) => a,
+        Err(e) => return (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+    };
+    let target = intent.stake_account.as_deref().map(str::trim).filter(|a| !a.is_empty());
+    let lamports = (intent.amount > 0.0).then_some((intent.amount * 1_000_000_000.0) as u64);
+
+    let built = match intent.action.as_str() {
+        "LIST_STAKES" => {
+            let total: u64 = accounts.iter().map(|a| a.lamports).sum();
+            return (StatusCode::OK, Json(AgentResponse {
+                action_type: "LIST_STAKES".to_string(),
+                tx_base64: None,
+                message: format!("{} stake account(s), {:.4} SOL total", accounts.len(), total as f64 / 1_000_000_000.0),
+                meta: Some(json!({ "stake_accounts": accounts, "total_lamports": total, "network": payload.network })),
+            })).into_response();
+        },
+        "UNSTAKE" => stake::pick_stake_account(&accounts, target, &[Activating, Active], "deactivate")
+            .and_then(|a| Ok((stake::build_deactivate_tx(&user, a)?, a, format!(
+                "Deactivating {:.4} SOL; withdrawable after the current epoch",
+                a.delegated as f64 / 1_000_000_000.0
+            )))),
+        "WITHDRAW_STAKE" => stake::pick_stake_account(&accounts, target, &[Inactive, Active, Activating, Deactivating], "withdraw from")
+            .and_then(|a| {
+                let (tx, amount) = stake::build_withdraw_tx(&user, a, lamports)?;
+                Ok((tx, a, format!("Withdrawing {:.4} SOL from stake account", amount as f64 / 1_000_000_000.0)))
+            }),
+        "MERGE_STAKE" => {
+            let source = intent.source_stake_account.as_deref().map(str::trim).filter(|a| !a.is_empty());
+            match (target, source) {
+                (Some(_), Some(_)) => stake::pick_stake_account(&accounts, target, &[Activating, Active, Inactive], "merge into")
+                    .and_then(|dest| {
+                        let src = stake::pick_stake_account(&accounts, source, &[Activating, Active, Inactive], "merge")?;
+                        Ok((stake::build_merge_tx(&user, dest, src)?, dest, "Merging stake accounts".to_string()))
+                    }),
+                _ => Err("Merging needs both the destination and the source stake account".to_string()),
+            }
+        },
+        "SPLIT_STAKE" => match lamports {
+            None => Err("How much SOL should be split off?".to_string()),
+            Some(lamports) => match stake::pick_stake_account(&accounts, target, &[Activating, Active, Inactive], "split") {
+                Ok(a) => stake::build_split_tx(&rpc, &user, a, lamports).await.map(|(tx, new_account)| {
+                    (tx, a, format!("Splitting {} SOL into new stake account {}", intent.amount, new_account))
+                }),
+                Err(e) => Err(e),
+            },
+        },
+        other => Err(format!("Unknown stake action {}", other)),
+    };
+
+    match built {
+        Ok((tx, account, message)) => respond(nonce, AgentResponse {
+            action_type: intent.action.clone(),
+            tx_base64: Some(tx),
+            meta: Some(json!({
+                "action": message,
+                "stake_account": account,
+                "stake_accounts": accounts,
+                "network": payload.network,
+                "fee": "~0.000005 SOL",
+            })),
+            message,
+        }),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    }
+}
+
+/// Authority actions on a mint the user controls: mint more, hand over or
+/// revoke an authority, freeze or thaw a holder's account.
+async fn manage_token(
+    intent: &ai::Intent,
+    payload: &UserRequest,
+    nonce: Option<&nonce::NonceInfo>,
+) -> axum::response::Response {
+    let user = match solana_sdk::pubkey::Pubkey::from_str(&payload.user_pubkey) {
+        Ok(p) => p,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid user pubkey: {}", e)))).into_response(),
+    };
+    let mint = match token::resolve_mint(&intent.token_in) {
+        Ok(m) => m,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    };
+    let recipient = match intent.recipient.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+        Some(r) => match solana_sdk::pubkey::Pubkey::from_str(r) {
+            Ok(p) => Some(p),
+            Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid recipient: {}", e)))).into_response(),
+        },
+        None => None,
+    };
+    let kind = match token::AuthorityKind::parse(intent.authority.as_deref().unwrap_or("")) {
+        Ok(k) => k,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    };
+    let rpc = rpc::client(&payload.network);
+
+    let built = match intent.action.as_str() {
+        "MINT_MORE" => {
+            let to = recipient.unwrap_or(user);
+            token::build_mint_to_tx(&rpc, &user, &mint, intent.amount, &to).await.map(|plan| (
+                plan.tx_base64,
+                json!({
+                    "action": "Mint Tokens",
+                    "amount": intent.amount,
+                    "amount_atomic": plan.amount_atomic,
+                    "decimals": plan.decimals,
+                    "recipient": to.to_string(),
+                    "token_account": plan.token_account.to_string(),
+                    "new_supply_atomic": plan.new_supply,
+                }),
+                format!("Minting {} of {} to {}", intent.amount, mint, to),
+            ))
+        },
+        "SET_AUTHORITY" => match recipient {
+            None => Err(format!("Who should receive the {} authority?", kind.label())),
+            Some(to) => token::build_set_authority_tx(&rpc, &user, &mint, kind, Some(&to)).await.map(|tx| (
+                tx,
+                json!({ "action": "Set Authority", "authority": kind.label(), "new_authority": to.to_string() }),
+                format!("Handing the {} authority of {} to {}", kind.label(), mint, to),
+            )),
+        },
+        "REVOKE_AUTHORITY" => token::build_set_authority_tx(&rpc, &user, &mint, kind, None).await.map(|tx| (
+            tx,
+            json!({ "action": "Revoke Authority", "authority": kind.label(), "new_authority": null, "irreversible": true }),
+            format!("Revoking the {} authority of {} for good", kind.label(), mint),
+        )),
+        "FREEZE" | "THAW" => match recipient {
+            None => Err("Which wallet's token account?".to_string()),
+            Some(holder) => {
+                let freeze = intent.action == "FREEZE";
+                token::build_freeze_tx(&rpc, &user, &mint, &holder, freeze).await.map(|(tx, account)| (
+                    tx,
+                    json!({
+                        "action": if freeze { "Freeze Account" } else { "Thaw Account" },
+                        "holder": holder.to_string(),
+                        "token_account": account.to_string(),
+                    }),
+                    format!("{} {}'s {} account", if freeze { "Freezing" } else { "Thawing" }, holder, mint),
+                ))
+            },
+        },
+        other => Err(format!("Unknown token action {}", other)),
+    };
+
+    let (tx, mut meta, message) = match built {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    };
+    if let serde_json::Value::Object(m) = &mut meta {
+        m.insert("mint".to_string(), json!(mint.to_string()));
+        m.insert("network".to_string(), json!(payload.network));
+        m.insert("fee".to_string(), json!("~0.000005 SOL"));
+    }
+
+    // A revoked authority can never be restored
+    if intent.action == "REVOKE_AUTHORITY" && !payload.confirm_irreversible {
+        return (StatusCode::PRECONDITION_REQUIRED, Json(AgentResponse {
+            action_type: "CONFIRM_REQUIRED".to_string(),
+            tx_base64: None,
+            meta: Some(meta),
+            message: format!("{}. This is permanent. Resend with confirm_irreversible: true to continue.", message),
+        })).into_response();
+    }
+
+    respond(nonce, AgentResponse {
+        action_type: intent.action.clone(),
+        tx_base64: Some(tx),
+        meta: Some(meta),
+        message,
+    })
+}
+
+/// Build and upload the off-chain metadata for MINT_NFT / MINT_CNFT.
+/// Returns (name, symbol, published) or the error response to send.
+async fn publish_nft_metadata(
+    state: &AppState,
+    intent: &ai::Intent,
+    payload: &UserRequest,
+) -> Result<(String, String, metadata::PublishedMetadata), axum::response::Response> {
+    let name = intent.nft_name.clone().unwrap_or("AI Gen".to_string());
+    let symbol = intent.nft_symbol.clone().unwrap_or("AI".to_string()).to_uppercase();
+
+    // Validate before uploading so a bad name doesn't leave orphaned files
+    if let Err(e) = nft::validate_metadata_fields(&name, &symbol, "") {
+        return Err((StatusCode::BAD_REQUEST, Json(json_err(e))).into_response());
+    }
+
+    let nft_metadata = metadata::build_metadata(
+        &name,
+        &symbol,
+        intent.nft_description.as_deref(),
+        intent.nft_attributes.clone(),
+        &payload.user_pubkey,
+    );
+    match metadata::publish(state.metadata_store.as_ref(), nft_metadata, payload.image.as_ref()).await {
+        Ok(p) => Ok((name, symbol, p)),
+        Err(e) => Err((StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response()),
+    }
+}
+
+/// Send a successful build, moving it onto the durable nonce when one is in use.
+fn respond(nonce: Option<&nonce::NonceInfo>, mut resp: AgentResponse) -> axum::response::Response {
+    if let (Some(info), Some(tx)) = (nonce, resp.tx_base64.as_deref()) {
+        match nonce::apply_durable_nonce(tx, info) {
+            Ok(wrapped) => resp.tx_base64 = Some(wrapped),
+            Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+        }
+        if let Some(serde_json::Value::Object(meta)) = resp.meta.as_mut() {
+            meta.insert("durable_nonce".to_string(), json!(info.address.to_string()));
+            meta.insert("keep_blockhash".to_string(), json!(true));
+        }
+    }
+    (StatusCode::OK, Json(resp)).into_response()
+}
+
+/// Send an ordered list of transactions. The first goes in `tx_base64`; the
+/// client signs the rest of `meta.transactions` one after another. A nonce
+/// only advances once per transaction, so durable mode takes a single one.
+fn respond_batch(
+    nonce: Option<&nonce::NonceInfo>,
+    action_type: &str,
+    transactions: Vec<String>,
+    mut meta: serde_json::Value,
+    message: String,
+) -> axum::response::Response {
+    if nonce.is_some() && transactions.len() > 1 {
+        return (StatusCode::BAD_REQUEST, Json(json_err(format!(
+            "This needs {} transactions in order, but durable nonce mode covers only one. Retry without durable_nonce.",
+            transactions.len()
+        )))).into_response();
+    }
+    if let serde_json::Value::Object(m) = &mut meta {
+        m.insert("batch_count".to_string(), json!(transactions.len()));
+        m.insert("transactions".to_string(), json!(transactions));
+    }
+    respond(nonce, AgentResponse {
+        action_type: action_type.to_string(),
+        tx_base64: transactions.first().cloned(),
+        meta: Some(meta),
+        message,
+    })
+}
+
 fn json_err(msg: String) -> AgentResponse {
     AgentResponse { action_type: "ERROR".into(), tx_base64: None, meta: None, message: msg }
 }
+
+// --- DURABLE NONCE MANAGEMENT ---
+#[derive(Deserialize, Debug)]
+struct NonceRequest {
+    user_pubkey: String,
+    #[serde(default = "default_network")]
+    network: String,
+    /// STATUS | CREATE | ADVANCE (cancel pending durable txs) | CLOSE
+    op: String,
+}
+
+async fn handle_nonce(Json(payload): Json<NonceRequest>) -> impl IntoResponse {
+    println!("[NONCE] op={} network={}", payload.op, payload.network);
+
+    let rpc = rpc::client(&payload.network);
+    let info = match nonce::fetch_nonce(&rpc, &payload.user_pubkey).await {
+        Ok(i) => i,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    };
+
+    match payload.op.to_uppercase().as_str() {
+        "STATUS" => {
+            let meta = match &info {
+                Some(i) => json!({ "exists": true, "address": i.address.to_string(), "nonce": i.blockhash.to_string(), "authority": i.authority.to_string(), "lamports": i.lamports, "network": payload.network }),
+                None => json!({ "exists": false, "network": payload.network }),
+            };
+            (StatusCode::OK, Json(AgentResponse {
+                action_type: "NONCE_STATUS".to_string(),
+                tx_base64: None,
+                meta: Some(meta),
+                message: if info.is_some() { "Durable nonce account is active".to_string() } else { "No durable nonce account yet".to_string() },
+            })).into_response()
+        },
+        "CREATE" => {
+            if info.is_some() {
+                return (StatusCode::BAD_REQUEST, Json(json_err("Durable nonce account already exists".into()))).into_response();
+            }
+            match nonce::build_create_nonce_tx(&rpc, &payload.user_pubkey).await {
+                Ok(tx) => (StatusCode::OK, Json(AgentResponse {
+                    action_type: "CREATE_NONCE".to_string(),
+                    tx_base64: Some(tx),
+                    meta: Some(json!({ "action": "Set up durable nonce", "network": payload.network, "fee": "~0.0015 SOL (refundable rent)" })),
+                    message: "Creating your durable nonce account".to_string(),
+                })).into_response(),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        op @ ("ADVANCE" | "CLOSE") => {
+            let info = match info {
+                Some(i) => i,
+                None => return (StatusCode::BAD_REQUEST, Json(json_err("No durable nonce account for this wallet".into()))).into_response(),
+            };
+
+            // ADVANCE is itself built on the nonce, so the client must keep its blockhash
+            let (action_type, label, keep_blockhash, built) = if op == "ADVANCE" {
+                ("ADVANCE_NONCE", "Cancel pending durable transactions", true, nonce::build_advance_nonce_tx(&payload.user_pubkey, &info))
+            } else {
+                ("CLOSE_NONCE", "Close durable nonce account", false, nonce::build_close_nonce_tx(&payload.user_pubkey, &info))
+            };
+
+            match built {
+                Ok(tx) => (StatusCode::OK, Json(AgentResponse {
+                    action_type: action_type.to_string(),
+                    tx_base64: Some(tx),
+                    meta: Some(json!({ "action": label, "address": info.address.to_string(), "lamports": info.lamports, "network": payload.network, "keep_blockhash": keep_blockhash })),
+                    message: label.to_string(),
+                })).into_response(),
+                Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+            }
+        },
+        _ => (StatusCode::BAD_REQUEST, Json(json_err(format!("Unknown nonce op '{}'. Supported: STATUS, CREATE, ADVANCE, CLOSE", payload.op)))).into_response(),
+    }
+}
+
+// --- SIGNED TRANSACTION SUBMISSION ---
+#[derive(Deserialize, Debug)]
+struct SubmitRequest {
+    /// Base64 of the fully signed transaction returned by the wallet
+    signed_tx: String,
+    #[serde(default = "default_network")]
+    network: String,
+    /// "confirmed" (default) or "finalized"
+    #[serde(default)]
+    commitment: Option<String>,
+}
+
+async fn handle_submit(Json(payload): Json<SubmitRequest>) -> impl IntoResponse {
+    let (tx, signature) = match submit::decode_signed_tx(&payload.signed_tx) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    };
+
+    let target = match payload.commitment.as_deref() {
+        None | Some("confirmed") => solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+        Some("finalized") => solana_sdk::commitment_config::CommitmentConfig::finalized(),
+        Some(other) => return (StatusCode::BAD_REQUEST, Json(json_err(
+            format!("Unsupported commitment '{}'. Use confirmed or finalized", other)
+        ))).into_response(),
+    };
+
+    println!("[SUBMIT] sig={} network={}", signature, payload.network);
+
+    let rpc = rpc::client(&payload.network);
+    let outcome = match submit::submit_and_confirm(&rpc, &tx, signature, target).await {
+        Ok(o) => o,
+        Err(e) => return (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+    };
+
+    println!("[SUBMIT] sig={} status={} attempts={}", outcome.signature, outcome.status, outcome.attempts);
+
+    let (code, action_type, message) = if outcome.landed() {
+        (StatusCode::OK, "SUBMIT", format!("Transaction {}", outcome.status))
+    } else if outcome.status == "pending" {
+        (StatusCode::ACCEPTED, "SUBMIT", "Transaction sent but not yet confirmed".to_string())
+    } else {
+        let reason = outcome.error.as_ref()
+            .and_then(|e| e["message"].as_str())
+            .unwrap_or("unknown error")
+            .to_string();
+        (StatusCode::UNPROCESSABLE_ENTITY, "ERROR", format!("Transaction {}: {}", outcome.status, reason))
+    };
+
+    let mut meta = serde_json::to_value(&outcome).unwrap_or_default();
+    meta["network"] = json!(payload.network);
+
+    (code, Json(AgentResponse {
+        action_type: action_type.to_string(),
+        tx_base64: None,
+        meta: Some(meta),
+        message,
+    })).into_response()
+}
+
+// --- VALIDATOR DIRECTORY ---
+#[derive(Deserialize, Debug)]
+struct ValidatorsQuery {
+    #[serde(default = "default_network")]
+    network: String,
+    #[serde(default = "default_validator_limit")]
+    limit: usize,
+}
+
+fn default_validator_limit() -> usize { 20 }
+
+async fn handle_validators(State(state): State<AppState>, Query(query): Query<ValidatorsQuery>) -> axum::response::Response {
+    let rpc = rpc::client(&query.network);
+    match state.validators.list(&rpc, &query.network).await {
+        Ok(list) => {
+            let top: Vec<_> = list.iter().take(query.limit).collect();
+            (StatusCode::OK, Json(AgentResponse {
+                action_type: "VALIDATORS".to_string(),
+                tx_base64: None,
+                message: format!("Top {} of {} validators on {}", top.len(), list.len(), query.network),
+                meta: Some(json!({ "validators": top, "network": query.network })),
+            })).into_response()
+        },
+        Err(e) => (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+    }
+}
+
+// --- TEST TOKEN FAUCET ---
+/// Test mints the faucet has created on a network, so wallets can show them
+async fn handle_faucet_mints(State(state): State<AppState>, Query(query): Query<NetworkQuery>) -> axum::response::Response {
+    let mints = state.test_mints.list(&query.network);
+    (StatusCode::OK, Json(AgentResponse {
+        action_type: "FAUCET_MINTS".to_string(),
+        tx_base64: None,
+        message: format!("{} test mints on {}", mints.len(), query.network),
+        meta: Some(json!({ "mints": mints, "network": query.network })),
+    })).into_response()
+}
+
+// --- RECURRING SCHEDULES ---
+#[derive(Deserialize, Debug)]
+struct OwnerQuery {
+    user_pubkey: String,
+}
+
+/// The client IP that per-client limits are keyed on. That is the peer address
+/// unless CLIENT_IP_HEADER names a header set by a trusted reverse proxy
+/// (e.g. `X-Real-IP`, or `X-Forwarded-For`, whose last entry the proxy
+/// appends). Only set it behind such a proxy: clients can send the header
+/// themselves. Behind a proxy without it, every client shares the proxy's IP.
+fn client_ip(headers: &axum::http::HeaderMap, peer: std::net::SocketAddr) -> std::net::IpAddr {
+    let Ok(name) = env::var("CLIENT_IP_HEADER") else {
+        return peer.ip();
+    };
+    headers.get(name.trim()).and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+        .unwrap_or(peer.ip())
+}
+
+/// Schedule management needs the wallet's signature over
+/// `schedule::owner_message(user_pubkey, X-Wallet-Timestamp)` in X-Wallet-Signature
+fn require_owner(headers: &axum::http::HeaderMap, user_pubkey: &str) -> Result<(), String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let timestamp: i64 = header("X-Wallet-Timestamp").trim().parse()
+        .map_err(|_| "X-Wallet-Timestamp (unix seconds) required".to_string())?;
+    let signature = header("X-Wallet-Signature");
+    if signature.is_empty() {
+        return Err("X-Wallet-Signature required: sign the schedule ownership message with your wallet".to_string());
+    }
+    schedule::verify_owner(user_pubkey, timestamp, signature, chrono::Utc::now())
+}
+
+/// Register `intent` to repeat on `cadence` instead of building it now
+async fn create_schedule(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+    intent: ai::Intent,
+    payload: &UserRequest,
+    cadence: &str,
+) -> axum::response::Response {
+    // Schedules run and notify on the owner's behalf, so only the owner can make one
+    if let Err(e) = require_owner(headers, &payload.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    if let Some(url) = payload.notify_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        if let Err(e) = schedule::resolve_notify_url(url).await {
+            return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response();
+        }
+    }
+    let created = state.schedules.create(schedule::NewSchedule {
+        user_pubkey: payload.user_pubkey.clone(),
+        network: payload.network.clone(),
+        durable_nonce: payload.durable_nonce,
+        description: payload.prompt.clone(),
+        intent,
+        cadence: cadence.to_string(),
+        notify_url: payload.notify_url.clone(),
+    });
+    match created {
+        Ok(s) => (StatusCode::OK, Json(AgentResponse {
+            action_type: "SCHEDULE_CREATED".to_string(),
+            tx_base64: None,
+            message: format!("Scheduled {}; first run {}", s.cadence.describe(), s.next_run.format("%a %d %b %H:%M UTC")),
+            meta: Some(json!({ "schedule": s, "network": payload.network })),
+        })).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    }
+}
+
+/// Same body as `/agent/execute`, but the request must describe a recurring intent
+async fn handle_create_schedule(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<UserRequest>,
+) -> axum::response::Response {
+    // Checked before parsing too, so a missing signature doesn't cost a model call
+    if let Err(e) = require_owner(&headers, &payload.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    let intent = match ai::parse_intent(&state.get_next_key(), &payload.prompt).await {
+        Ok(i) => i,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json_err(e.to_string()))).into_response(),
+    };
+    match payload.schedule.clone().or(intent.schedule.clone()).filter(|c| !c.trim().is_empty()) {
+        Some(cadence) => create_schedule(&state, &headers, intent, &payload, &cadence).await,
+        None => (StatusCode::BAD_REQUEST, Json(json_err("How often should this repeat? Say e.g. \"every Monday\" or pass schedule.".to_string()))).into_response(),
+    }
+}
+
+async fn handle_list_schedules(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(owner): Query<OwnerQuery>,
+) -> axum::response::Response {
+    if let Err(e) = require_owner(&headers, &owner.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    let list = state.schedules.list(&owner.user_pubkey);
+    (StatusCode::OK, Json(AgentResponse {
+        action_type: "SCHEDULES".to_string(),
+        tx_base64: None,
+        message: format!("{} schedule(s), {} waiting for a signature", list.len(), list.iter().filter(|s| s.pending.is_some()).count()),
+        meta: Some(json!({ "schedules": list })),
+    })).into_response()
+}
+
+async fn handle_get_schedule(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(owner): Query<OwnerQuery>,
+) -> axum::response::Response {
+    if let Err(e) = require_owner(&headers, &owner.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    match state.schedules.get(&id, &owner.user_pubkey) {
+        Some(s) => (StatusCode::OK, Json(AgentResponse {
+            action_type: "SCHEDULE".to_string(),
+            tx_base64: None,
+            message: format!("{} - next run {}", s.cadence.describe(), s.next_run.format("%a %d %b %H:%M UTC")),
+            meta: Some(json!({ "schedule": s })),
+        })).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(json_err(format!("No schedule {}", id)))).into_response(),
+    }
+}
+
+async fn handle_update_schedule(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(owner): Query<OwnerQuery>,
+    Json(update): Json<schedule::ScheduleUpdate>,
+) -> axum::response::Response {
+    if let Err(e) = require_owner(&headers, &owner.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    if let Some(url) = update.notify_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        if let Err(e) = schedule::resolve_notify_url(url).await {
+            return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response();
+        }
+    }
+    match state.schedules.update(&id, &owner.user_pubkey, update) {
+        Ok(s) => (StatusCode::OK, Json(AgentResponse {
+            action_type: "SCHEDULE_UPDATED".to_string(),
+            tx_base64: None,
+            message: if s.active {
+                format!("{} - next run {}", s.cadence.describe(), s.next_run.format("%a %d %b %H:%M UTC"))
+            } else {
+                "Schedule paused".to_string()
+            },
+            meta: Some(json!({ "schedule": s })),
+        })).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    }
+}
+
+async fn handle_delete_schedule(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(owner): Query<OwnerQuery>,
+) -> axum::response::Response {
+    if let Err(e) = require_owner(&headers, &owner.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    match state.schedules.delete(&id, &owner.user_pubkey) {
+        Ok(()) => (StatusCode::OK, Json(AgentResponse {
+            action_type: "SCHEDULE_DELETED".to_string(),
+            tx_base64: None,
+            message: "Schedule deleted".to_string(),
+            meta: Some(json!({ "id": id })),
+        })).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, Json(json_err(e))).into_response(),
+    }
+}
+
+/// Build the schedule's transaction right now, e.g. when the pending one's
+/// blockhash has expired. Clears the pending run; the cadence is unchanged.
+async fn handle_run_schedule(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(owner): Query<OwnerQuery>,
+) -> axum::response::Response {
+    if let Err(e) = require_owner(&headers, &owner.user_pubkey) {
+        return (StatusCode::UNAUTHORIZED, Json(json_err(e))).into_response();
+    }
+    let Some(s) = state.schedules.get(&id, &owner.user_pubkey) else {
+        return (StatusCode::NOT_FOUND, Json(json_err(format!("No schedule {}", id)))).into_response();
+    };
+    if let Err(e) = state.schedules.record_run(&id, None, false) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json_err(e))).into_response();
+    }
+    build_scheduled_run(&state, &s).await
+}
+
+/// One run of a schedule, built as if the user had just sent the prompt
+async fn build_scheduled_run(state: &AppState, s: &schedule::Schedule) -> axum::response::Response {
+    let payload = UserRequest {
+        prompt: s.description.clone(),
+        user_pubkey: s.user_pubkey.clone(),
+        network: s.network.clone(),
+        durable_nonce: s.durable_nonce,
+        image: None,
+        confirm_irreversible: false,
+        csv: None,
+        schedule: None,
+        notify_url: None,
+        client_ip: None,
+    };
+    execute_intent(state, s.intent.clone(), &payload).await
+}
+
+/// Background loop: build each due run, park it on the schedule and notify
+/// the user. Without a durable nonce the parked transaction's blockhash
+/// expires within minutes; `/schedules/:id/run` rebuilds it on demand.
+async fn run_schedules(state: AppState) {
+    let mut tick = tokio::time::interval(schedule::TICK);
+    loop {
+        tick.tick().await;
+        for due in state.schedules.due(chrono::Utc::now()) {
+            let response = build_scheduled_run(&state, &due).await;
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or(serde_json::Value::Null);
+            println!("[SCHEDULE] {} due {} -> {}", due.id, due.next_run, body["action_type"]);
+
+            let pending = schedule::PendingRun { due_at: due.next_run, built_at: chrono::Utc::now(), response: body };
+            if let Err(e) = state.schedules.record_run(&due.id, Some(pending.clone()), true) {
+                println!("[SCHEDULE] {} failed to save run: {}", due.id, e);
+            }
+            schedule::notify(&due, &pending).await;
+        }
+    }
+}
+
+// --- TRANSACTION STATUS STREAM (SSE) ---
+#[derive(Deserialize, Debug)]
+struct StreamQuery {
+    signature: String,
+    #[serde(default = "default_network")]
+    network: String,
+    /// Wallet to report live balance changes for
+    #[serde(default)]
+    owner: Option<String>,
+}
+
+async fn handle_stream(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(peer): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    headers: axum::http::HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> axum::response::Response {
+    let signature = match solana_sdk::signature::Signature::from_str(&query.signature) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid signature: {}", e)))).into_response(),
+    };
+    let owner = match query.owner.as_deref().map(solana_sdk::pubkey::Pubkey::from_str).transpose() {
+        Ok(o) => o,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(format!("Invalid owner pubkey: {}", e)))).into_response(),
+    };
+
+    println!("[STREAM] sig={} network={}", signature, query.network);
+
+    let slot = match state.streams.acquire(client_ip(&headers, peer)) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::TOO_MANY_REQUESTS, Json(json_err(e))).into_response(),
+    };
+    let rx = stream::watch_signature(query.network, signature, owner, slot);
+    let events = futures::stream::unfold(rx, |mut rx| async move {
+        let (name, data) = rx.recv().await?;
+        Some((Ok::<_, std::convert::Infallible>(Event::default().event(name).data(data.to_string())), rx))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+// --- LOCAL METADATA FILES ---
+async fn serve_metadata(
+    State(state): State<AppState>,
+    axum::extract::Path(file): axum::extract::Path<String>,
+) -> axum::response::Response {
+    let path = match state.metadata_store.local_path(&file) {
+        Some(p) => p,
+        None => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    let content_type = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("jpg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => ([(axum::http::header::CONTENT_TYPE, content_type)], bytes).into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+// --- ADMIN: COMPRESSED NFT TREES ---
+/// Admin endpoints are disabled unless ADMIN_TOKEN is set
+fn is_admin(headers: &axum::http::HeaderMap) -> bool {
+    let expected = env::var("ADMIN_TOKEN").unwrap_or_default();
+    let given = headers.get("X-Admin-Token").and_then(|v| v.to_str().ok()).unwrap_or_default();
+    !expected.is_empty() && given == expected
+}
+
+fn admin_denied() -> axum::response::Response {
+    (StatusCode::UNAUTHORIZED, Json(json_err("Admin token required".into()))).into_response()
+}
+
+#[derive(Deserialize, Debug)]
+struct TreeRequest {
+    max_depth: u32,
+    max_buffer_size: u32,
+    #[serde(default)]
+    canopy_depth: u32,
+    #[serde(default = "default_network")]
+    network: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct NetworkQuery {
+    #[serde(default = "default_network")]
+    network: String,
+}
+
+async fn handle_size_tree(headers: axum::http::HeaderMap, Json(req): Json<TreeRequest>) -> axum::response::Response {
+    if !is_admin(&headers) {
+        return admin_denied();
+    }
+
+    let rpc = rpc::client(&req.network);
+    match cnft::size_tree(&rpc, req.max_depth, req.max_buffer_size, req.canopy_depth).await {
+        Ok(sizing) => (StatusCode::OK, Json(AgentResponse {
+            action_type: "TREE_SIZE".to_string(),
+            tx_base64: None,
+            message: format!("Tree holds {} cNFTs for {:.4} SOL rent", sizing.capacity, sizing.rent_lamports as f64 / 1_000_000_000.0),
+            meta: Some(json!(sizing)),
+        })).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    }
+}
+
+async fn handle_create_tree(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<TreeRequest>,
+) -> axum::response::Response {
+    if !is_admin(&headers) {
+        return admin_denied();
+    }
+    let operator = match &state.operator {
+        Some(k) => k.clone(),
+        None => return (StatusCode::SERVICE_UNAVAILABLE, Json(json_err("OPERATOR_KEYPAIR not configured".into()))).into_response(),
+    };
+
+    let rpc = rpc::client(&req.network);
+    let sizing = match cnft::size_tree(&rpc, req.max_depth, req.max_buffer_size, req.canopy_depth).await {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json_err(e))).into_response(),
+    };
+
+    println!("[ADMIN] Creating cNFT tree depth={} buffer={} canopy={} network={}", req.max_depth, req.max_buffer_size, req.canopy_depth, req.network);
+
+    let tree = match cnft::create_tree(&rpc, &operator, &sizing).await {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_GATEWAY, Json(json_err(e))).into_response(),
+    };
+
+    let record = cnft::TreeRecord {
+        network: req.network.clone(),
+        address: tree.to_string(),
+        max_depth: sizing.max_depth,
+        max_buffer_size: sizing.max_buffer_size,
+        canopy_depth: sizing.canopy_depth,
+        capacity: sizing.capacity,
+        rent_lamports: sizing.rent_lamports,
+    };
+    if let Err(e) = state.trees.add(record.clone()) {
+        // The tree exists on-chain either way; surface its address so it isn't lost
+        eprintln!("[ADMIN] Tree {} created but not saved: {}", tree, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json_err(format!("Tree {} created but not saved: {}", tree, e)))).into_response();
+    }
+
+    (StatusCode::OK, Json(AgentResponse {
+        action_type: "TREE_CREATED".to_string(),
+        tx_base64: None,
+        message: format!("Created cNFT tree {}", tree),
+        meta: Some(json!({ "tree": record, "sizing": sizing })),
+    })).into_response()
+}
+
+async fn handle_list_trees(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(query): Query<NetworkQuery>,
+) -> axum::response::Response {
+    if !is_admin(&headers) {
+        return admin_denied();
+    }
+
+    let rpc = rpc::client(&query.network);
+    let mut trees = vec![];
+    for record in state.trees.list(&query.network) {
+        let minted = match solana_sdk::pubkey::Pubkey::from_str(&record.address) {
+            Ok(tree) => cnft::fetch_num_minted(&rpc, &tree).await.ok(),
+            Err(_) => None,
+        };
+        trees.push(json!({ "tree": record, "num_minted": minted }));
+    }
+
+    (StatusCode::OK, Json(AgentResponse {
+        action_type: "TREES".to_string(),
+        tx_base64: None,
+        message: format!("{} tree(s) on {}", trees.len(), query.network),
+        meta: Some(json!({ "trees": trees, "network": query.network })),
+    })).into_response()
+}
@@ -0,0 +1,338 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use base64::{engine::general_purpose, Engine as _};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── NFT METADATA (METAPLEX JSON STANDARD) ───────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Largest image we accept inline with a request (decoded bytes)
+pub const MAX_IMAGE_BYTES: usize = 1_500_000;
+
+/// Body limit for routes that take an inline image: the image as base64
+/// plus room for the rest of the request. Above axum's 2 MB default.
+pub const MAX_REQUEST_BYTES: usize = MAX_IMAGE_BYTES.div_ceil(3) * 4 + 256 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attribute {
+    pub trait_type: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MetadataFile {
+    pub uri: String,
+    #[serde(rename = "type")]
+    pub content_type: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MetadataCreator {
+    pub address: String,
+    pub share: u8,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MetadataProperties {
+    pub files: Vec<MetadataFile>,
+    pub category: String,
+    pub creators: Vec<MetadataCreator>,
+}
+
+/// Off-chain JSON the on-chain `uri` points at
+#[derive(Serialize, Debug, Clone)]
+pub struct NftMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    pub attributes: Vec<Attribute>,
+    pub properties: MetadataProperties,
+}
+
+/// Image sent along with the request, base64-encoded
+#[derive(Deserialize, Debug)]
+pub struct UploadedImage {
+    pub data_base64: String,
+    pub content_type: String,
+}
+
+/// Result of publishing metadata, echoed in `meta`
+#[derive(Serialize, Debug)]
+pub struct PublishedMetadata {
+    pub uri: String,
+    pub content_hash: String,
+    pub image_uri: Option<String>,
+    pub image_hash: Option<String>,
+    pub store: &'static str,
+}
+
+/// Build Metaplex-standard metadata from the parsed intent
+pub fn build_metadata(
+    name: &str,
+    symbol: &str,
+    description: Option<&str>,
+    attributes: Vec<Attribute>,
+    creator: &str,
+) -> NftMetadata {
+    NftMetadata {
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        description: description.map(str::to_string).unwrap_or_else(|| format!("{} - minted with Solana AI Agent", name)),
+        image: None,
+        attributes,
+        properties: MetadataProperties {
+            files: vec![],
+            category: "image".to_string(),
+            creators: vec![MetadataCreator { address: creator.to_string(), share: 100 }],
+        },
+    }
+}
+
+/// Upload the image (if any), then the metadata JSON pointing at it
+pub async fn publish(
+    store: &dyn MetadataStore,
+    mut metadata: NftMetadata,
+    image: Option<&UploadedImage>,
+) -> Result<PublishedMetadata, String> {
+    let (mut image_uri, mut image_hash) = (None, None);
+
+    if let Some(img) = image {
+        if !img.content_type.starts_with("image/") {
+            return Err(format!("Unsupported image type '{}'", img.content_type));
+        }
+        let bytes = general_purpose::STANDARD.decode(img.data_base64.trim())
+            .map_err(|e| format!("Invalid image base64: {}", e))?;
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(format!("Image too large ({} bytes, max {})", bytes.len(), MAX_IMAGE_BYTES));
+        }
+
+        image_hash = Some(content_hash(&bytes));
+        let uri = store.put(bytes, &img.content_type).await?;
+        metadata.image = Some(uri.clone());
+        metadata.properties.files.push(MetadataFile { uri: uri.clone(), content_type: img.content_type.clone() });
+        image_uri = Some(uri);
+    }
+
+    let json = serde_json::to_vec(&metadata)
+        .map_err(|e| format!("Metadata serialize error: {}", e))?;
+    let hash = content_hash(&json);
+    let uri = store.put(json, "application/json").await?;
+
+    Ok(PublishedMetadata { uri, content_hash: hash, image_uri, image_hash, store: store.name() })
+}
+
+/// Hex SHA-256, used both for `meta` and as the local store's file name
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn extension_for(content_type: &str) -> &'static str {
+    match content_type {
+        "application/json" => "json",
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        _ => "bin",
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ─── STORAGE BACKENDS ────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Somewhere to put metadata JSON and images. Returns a public URI.
+#[async_trait]
+pub trait MetadataStore: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn put(&self, bytes: Vec<u8>, content_type: &str) -> Result<String, String>;
+
+    /// On-disk path for a stored file, for backends this server serves itself
+    fn local_path(&self, _file: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Pick the backend from METADATA_STORE (local | ipfs | arweave). Defaults to local.
+pub fn store_from_env() -> Arc<dyn MetadataStore> {
+    match env::var("METADATA_STORE").unwrap_or_default().to_lowercase().as_str() {
+        "ipfs" => Arc::new(IpfsStore {
+            jwt: env::var("PINATA_JWT").unwrap_or_default(),
+            gateway: env::var("IPFS_GATEWAY").unwrap_or("https://gateway.pinata.cloud/ipfs".to_string()),
+        }),
+        "arweave" => Arc::new(ArweaveStore {
+            upload_url: env::var("ARWEAVE_UPLOAD_URL").unwrap_or_default(),
+            api_key: env::var("ARWEAVE_UPLOAD_KEY").ok(),
+            gateway: env::var("ARWEAVE_GATEWAY").unwrap_or("https://arweave.net".to_string()),
+        }),
+        _ => {
+            let port = env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+            Arc::new(LocalStore {
+                dir: PathBuf::from(env::var("METADATA_DIR").unwrap_or("./metadata".to_string())),
+                base_url: env::var("METADATA_BASE_URL").unwrap_or(format!("http://localhost:{}/metadata", port)),
+            })
+        }
+    }
+}
+
+// ─── LOCAL (DEV / TEST) ─────────────────────────────────────
+
+/// Content-addressed files on disk, served back by the `/metadata/:file` route.
+/// Only reachable by wallets if METADATA_BASE_URL is publicly routable.
+pub struct LocalStore {
+    pub dir: PathBuf,
+    pub base_url: String,
+}
+
+#[async_trait]
+impl MetadataStore for LocalStore {
+    fn name(&self) -> &'static str { "local" }
+
+    /// Only plain `<sha256>.<ext>` names resolve, so requests can't escape the dir
+    fn local_path(&self, file: &str) -> Option<PathBuf> {
+        let (stem, ext) = file.split_once('.')?;
+        let valid = stem.len() == 64
+            && stem.chars().all(|c| c.is_ascii_hexdigit())
+            && ext.chars().all(|c| c.is_ascii_alphanumeric());
+        valid.then(|| self.dir.join(file))
+    }
+
+    async fn put(&self, bytes: Vec<u8>, content_type: &str) -> Result<String, String> {
+        let file = format!("{}.{}", content_hash(&bytes), extension_for(content_type));
+        tokio::fs::create_dir_all(&self.dir).await
+            .map_err(|e| format!("Failed to create metadata dir: {}", e))?;
+        tokio::fs::write(self.dir.join(&file), bytes).await
+            .map_err(|e| format!("Failed to write metadata file: {}", e))?;
+        Ok(format!("{}/{}", self.base_url.trim_end_matches('/'), file))
+    }
+}
+
+// ─── IPFS (PINATA) ──────────────────────────────────────────
+
+/// Pins files through Pinata's `pinFileToIPFS`. Requires PINATA_JWT.
+pub struct IpfsStore {
+    pub jwt: String,
+    pub gateway: String,
+}
+
+#[async_trait]
+impl MetadataStore for IpfsStore {
+    fn name(&self) -> &'static str { "ipfs" }
+
+    async fn put(&self, bytes: Vec<u8>, content_type: &str) -> Result<String, String> {
+        if self.jwt.is_empty() {
+            return Err("PINATA_JWT not set in .env".to_string());
+        }
+
+        let file_name = format!("{}.{}", content_hash(&bytes), extension_for(content_type));
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(file_name)
+            .mime_str(content_type)
+            .map_err(|e| format!("Invalid content type: {}", e))?;
+        let form = reqwest::multipart::Form::new().part("file", part);
+
+        let res = Client::new()
+            .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
+            .bearer_auth(&self.jwt)
+            .multipart(form)
+            .send().await
+            .map_err(|e| format!("IPFS upload failed: {}", e))?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(format!("IPFS upload error ({}): {}", status, body));
+        }
+
+        let body: serde_json::Value = res.json().await
+            .map_err(|e| format!("IPFS response parse error: {}", e))?;
+        let cid = body["IpfsHash"].as_str()
+            .ok_or("IPFS response missing IpfsHash")?;
+
+        Ok(format!("{}/{}", self.gateway.trim_end_matches('/'), cid))
+    }
+}
+
+// ─── ARWEAVE ────────────────────────────────────────────────
+
+/// Uploads raw bytes to an Arweave bundler endpoint (ARWEAVE_UPLOAD_URL) that
+/// signs and funds the data item and answers with `{ "id": "<tx id>" }`.
+pub struct ArweaveStore {
+    pub upload_url: String,
+    pub api_key: Option<String>,
+    pub gateway: String,
+}
+
+#[async_trait]
+impl MetadataStore for ArweaveStore {
+    fn name(&self) -> &'static str { "arweave" }
+
+    async fn put(&self, bytes: Vec<u8>, content_type: &str) -> Result<String, String> {
+        if self.upload_url.is_empty() {
+            return Err("ARWEAVE_UPLOAD_URL not set in .env".to_string());
+        }
+
+        let mut req = Client::new()
+            .post(&self.upload_url)
+            .header("Content-Type", content_type)
+            .body(bytes);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+
+        let res = req.send().await
+            .map_err(|e| format!("Arweave upload failed: {}", e))?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(format!("Arweave upload error ({}): {}", status, body));
+        }
+
+        let body: serde_json::Value = res.json().await
+            .map_err(|e| format!("Arweave response parse error: {}", e))?;
+        let id = body["id"].as_str()
+            .ok_or("Arweave response missing id")?;
+
+        Ok(format!("{}/{}", self.gateway.trim_end_matches('/'), id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_store_only_resolves_content_hashes() {
+        let store = LocalStore { dir: PathBuf::from("/data/metadata"), base_url: String::new() };
+        let hash = content_hash(b"{}");
+        assert_eq!(store.local_path(&format!("{}.json", hash)), Some(store.dir.join(format!("{}.json", hash))));
+
+        for file in [
+            "../secrets.json".to_string(),
+            format!("../{}.json", &hash[3..]),
+            format!("{}.json/../../etc", hash),
+            format!("{}./json", hash),
+            format!("{}.json", &hash[1..]),
+            "..".to_string(),
+            "/etc/passwd".to_string(),
+            hash.clone(),
+        ] {
+            assert_eq!(store.local_path(&file), None, "{}", file);
+        }
+    }
+
+    #[test]
+    fn request_limit_fits_the_largest_image() {
+        let image = vec![0u8; MAX_IMAGE_BYTES];
+        assert!(general_purpose::STANDARD.encode(image).len() < MAX_REQUEST_BYTES);
+    }
+}
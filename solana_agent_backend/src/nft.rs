@@ -0,0 +1,347 @@
+use mpl_token_metadata::accounts::{MasterEdition, Metadata, TokenRecord};
+use mpl_token_metadata::instructions::{CreateMasterEditionV3Builder, CreateMetadataAccountV3Builder, TransferV1Builder};
+use mpl_token_metadata::types::{Creator, DataV2, ProgrammableConfig, TokenStandard};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    message::Message,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::nonce::NonceInfo;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── NFT MINTING (SPL TOKEN + METAPLEX TOKEN METADATA) ───────
+// ═══════════════════════════════════════════════════════════════
+
+/// Token Metadata field limits, enforced on-chain
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_SYMBOL_LEN: usize = 10;
+pub const MAX_URI_LEN: usize = 200;
+
+/// Addresses of a freshly built NFT mint
+pub struct NftMint {
+    pub tx_base64: String,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub metadata: Pubkey,
+    pub master_edition: Pubkey,
+}
+
+/// Validate name/symbol/uri against the Token Metadata limits
+pub fn validate_metadata_fields(name: &str, symbol: &str, uri: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("NFT name cannot be empty".to_string());
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(format!("NFT name too long ({} bytes, max {})", name.len(), MAX_NAME_LEN));
+    }
+    if symbol.len() > MAX_SYMBOL_LEN {
+        return Err(format!("NFT symbol too long ({} bytes, max {})", symbol.len(), MAX_SYMBOL_LEN));
+    }
+    if uri.len() > MAX_URI_LEN {
+        return Err(format!("Metadata URI too long ({} bytes, max {})", uri.len(), MAX_URI_LEN));
+    }
+    Ok(())
+}
+
+/// Build a 1-of-1 NFT mint: new mint account, the user's ATA, one token,
+/// metadata and a master edition (max supply 0).
+///
+/// The mint keypair is generated here and partially signs the transaction,
+/// so the blockhash (or durable nonce) is fixed server-side and the client
+/// must not replace it. The user is payer, mint/freeze authority before the
+/// master edition takes over, and update authority.
+pub async fn build_mint_nft_tx(
+    rpc: &RpcClient,
+    user: &str,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+    nonce: Option<&NonceInfo>,
+) -> Result<NftMint, String> {
+    validate_metadata_fields(name, symbol, uri)?;
+
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let token_account = get_associated_token_address(&user_pub, &mint);
+    let (metadata, _) = Metadata::find_pda(&mint);
+    let (master_edition, _) = MasterEdition::find_pda(&mint);
+
+    let mint_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN).await
+        .map_err(|e| format!("Failed to fetch mint rent: {}", e))?;
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &user_pub,
+            &mint,
+            mint_rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), &mint, &user_pub, Some(&user_pub), 0)
+            .map_err(|e| format!("Failed to build initialize_mint ix: {}", e))?,
+        create_associated_token_account_idempotent(&user_pub, &user_pub, &mint, &spl_token::id()),
+        spl_token::instruction::mint_to(&spl_token::id(), &mint, &token_account, &user_pub, &[], 1)
+            .map_err(|e| format!("Failed to build mint_to ix: {}", e))?,
+        CreateMetadataAccountV3Builder::new()
+            .metadata(metadata)
+            .mint(mint)
+            .mint_authority(user_pub)
+            .payer(user_pub)
+            .update_authority(user_pub, true)
+            .data(DataV2 {
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+                uri: uri.to_string(),
+                seller_fee_basis_points: 0,
+                creators: Some(vec![Creator { address: user_pub, verified: true, share: 100 }]),
+                collection: None,
+                uses: None,
+            })
+            .is_mutable(true)
+            .instruction(),
+        CreateMasterEditionV3Builder::new()
+            .edition(master_edition)
+            .mint(mint)
+            .update_authority(user_pub)
+            .mint_authority(user_pub)
+            .payer(user_pub)
+            .metadata(metadata)
+            .max_supply(0)
+            .instruction(),
+    ];
+
+    let (msg, blockhash) = match nonce {
+        Some(info) => (
+            Message::new_with_nonce(instructions, Some(&user_pub), &info.address, &info.authority),
+            info.blockhash,
+        ),
+        None => (
+            Message::new(&instructions, Some(&user_pub)),
+            rpc.get_latest_blockhash().await
+                .map_err(|e| format!("Failed to fetch blockhash: {}", e))?,
+        ),
+    };
+
+    let mut tx = Transaction::new_unsigned(msg);
+    tx.try_partial_sign(&[&mint_kp], blockhash)
+        .map_err(|e| format!("Failed to sign with mint keypair: {}", e))?;
+
+    Ok(NftMint {
+        tx_base64: general_purpose::STANDARD.encode(
+            bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+        ),
+        mint,
+        token_account,
+        metadata,
+        master_edition,
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ─── NFT TRANSFERS ───────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Metaplex Token Auth Rules program, needed for pNFTs with a rule set
+const AUTH_RULES_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+
+/// pNFT transfers run rule-set checks and blow through the default 200k CU
+const PNFT_TRANSFER_COMPUTE_UNITS: u32 = 400_000;
+
+/// Max accounts per getMultipleAccounts call
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// An NFT in the user's wallet
+pub struct OwnedNft {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub name: String,
+    pub metadata: Metadata,
+}
+
+impl OwnedNft {
+    pub fn is_programmable(&self) -> bool {
+        matches!(
+            self.metadata.token_standard,
+            Some(TokenStandard::ProgrammableNonFungible) | Some(TokenStandard::ProgrammableNonFungibleEdition)
+        )
+    }
+}
+
+/// Find one of the owner's NFTs by mint address or (case-insensitive) name.
+/// Compressed NFTs have no token account, so they never show up here.
+pub async fn find_owned_nft(rpc: &RpcClient, owner: &Pubkey, query: &str) -> Result<OwnedNft, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Which NFT? Give its name or mint address".to_string());
+    }
+
+    let held = nft_token_accounts(rpc, owner).await?;
+
+    // By mint address
+    if let Ok(mint) = Pubkey::from_str(query) {
+        let token_account = match held.iter().find(|(_, m)| *m == mint) {
+            Some((ta, _)) => *ta,
+            None => {
+                let exists = rpc.get_account_with_commitment(&mint, rpc.commitment()).await
+                    .map_err(|e| format!("Failed to fetch {}: {}", mint, e))?
+                    .value
+                    .is_some();
+                // cNFT asset ids are PDAs with no account behind them
+                return Err(if exists {
+                    format!("You don't hold NFT {}", mint)
+                } else {
+                    format!("{} has no on-chain account - if it's a compressed NFT, transferring it needs Merkle proofs from a DAS indexer, which this server doesn't have", mint)
+                });
+            }
+        };
+        let (metadata_pda, _) = Metadata::find_pda(&mint);
+        let data = rpc.get_account_data(&metadata_pda).await
+            .map_err(|e| format!("NFT {} has no Metaplex metadata: {}", mint, e))?;
+        let metadata = Metadata::safe_deserialize(&data)
+            .map_err(|e| format!("Invalid metadata for {}: {}", mint, e))?;
+        return Ok(OwnedNft { mint, token_account, name: clean_name(&metadata.name), metadata });
+    }
+
+    // By name: load metadata for every NFT-shaped token account
+    let mut owned = vec![];
+    for chunk in held.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let pdas: Vec<Pubkey> = chunk.iter().map(|(_, mint)| Metadata::find_pda(mint).0).collect();
+        let accounts = rpc.get_multiple_accounts(&pdas).await
+            .map_err(|e| format!("Failed to fetch NFT metadata: {}", e))?;
+        for ((token_account, mint), account) in chunk.iter().zip(accounts) {
+            let metadata = match account.and_then(|a| Metadata::safe_deserialize(&a.data).ok()) {
+                Some(m) => m,
+                None => continue,
+            };
+            owned.push(OwnedNft { mint: *mint, token_account: *token_account, name: clean_name(&metadata.name), metadata });
+        }
+    }
+
+    let wanted = query.to_lowercase();
+    let exact: Vec<usize> = (0..owned.len()).filter(|&i| owned[i].name.to_lowercase() == wanted).collect();
+    let matches = if exact.is_empty() {
+        (0..owned.len()).filter(|&i| owned[i].name.to_lowercase().contains(&wanted)).collect()
+    } else {
+        exact
+    };
+
+    match matches.as_slice() {
+        [i] => Ok(owned.swap_remove(*i)),
+        [] => Err(format!(
+            "No NFT named '{}' in your wallet. Compressed NFTs can't be transferred here (no Merkle proof source).",
+            query
+        )),
+        many => Err(format!(
+            "'{}' matches {} NFTs ({}). Use the mint address instead.",
+            query,
+            many.len(),
+            many.iter().take(5).map(|&i| owned[i].name.as_str()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// (token account, mint) for every SPL Token account holding exactly 1 of a 0-decimal mint
+async fn nft_token_accounts(rpc: &RpcClient, owner: &Pubkey) -> Result<Vec<(Pubkey, Pubkey)>, String> {
+    let accounts = rpc.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(spl_token::id())).await
+        .map_err(|e| format!("Failed to fetch token accounts: {}", e))?;
+
+    Ok(accounts.into_iter().filter_map(|keyed| {
+        let data = serde_json::to_value(&keyed.account.data).ok()?;
+        let info = &data["parsed"]["info"];
+        let amount = &info["tokenAmount"];
+        if amount["amount"].as_str()? != "1" || amount["decimals"].as_u64()? != 0 {
+            return None;
+        }
+        let mint = Pubkey::from_str(info["mint"].as_str()?).ok()?;
+        Some((Pubkey::from_str(&keyed.pubkey).ok()?, mint))
+    }).collect())
+}
+
+/// Metadata names are NUL-padded on-chain
+fn clean_name(name: &str) -> String {
+    name.trim_matches(char::from(0)).trim().to_string()
+}
+
+/// Build the transfer for an owned NFT: a plain SPL `transfer_checked` for
+/// legacy NFTs, or Token Metadata `transfer` with token records for pNFTs.
+pub fn build_transfer_nft_tx(
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    nft: &OwnedNft,
+    memo: Option<&str>,
+) -> Result<String, String> {
+    let destination = get_associated_token_address(recipient, &nft.mint);
+    let mut instructions = vec![];
+
+    if nft.is_programmable() {
+        let (metadata, _) = Metadata::find_pda(&nft.mint);
+        let (edition, _) = MasterEdition::find_pda(&nft.mint);
+        let (token_record, _) = TokenRecord::find_pda(&nft.mint, &nft.token_account);
+        let (destination_record, _) = TokenRecord::find_pda(&nft.mint, &destination);
+
+        let mut builder = TransferV1Builder::new();
+        builder
+            .token(nft.token_account)
+            .token_owner(*owner)
+            .destination_token(destination)
+            .destination_owner(*recipient)
+            .mint(nft.mint)
+            .metadata(metadata)
+            .edition(Some(edition))
+            .token_record(Some(token_record))
+            .destination_token_record(Some(destination_record))
+            .authority(*owner)
+            .payer(*owner)
+            .amount(1);
+        if let Some(ProgrammableConfig::V1 { rule_set: Some(rule_set) }) = nft.metadata.programmable_config {
+            builder
+                .authorization_rules_program(Some(AUTH_RULES_PROGRAM_ID))
+                .authorization_rules(Some(rule_set));
+        }
+
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(PNFT_TRANSFER_COMPUTE_UNITS));
+        // The program creates the destination ATA itself
+        instructions.push(builder.instruction());
+    } else {
+        instructions.push(create_associated_token_account_idempotent(owner, recipient, &nft.mint, &spl_token::id()));
+        instructions.push(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                &nft.token_account,
+                &nft.mint,
+                &destination,
+                owner,
+                &[],
+                1,
+                0,
+            ).map_err(|e| format!("Failed to build transfer ix: {}", e))?
+        );
+    }
+
+    if let Some(memo) = memo {
+        crate::swap::validate_memo(memo)?;
+        instructions.push(crate::swap::memo_ix(memo, owner));
+    }
+
+    let msg = Message::new(&instructions, Some(owner));
+    Ok(general_purpose::STANDARD.encode(
+        bincode::serialize(&Transaction::new_unsigned(msg)).map_err(|e| format!("Serialize error: {}", e))?
+    ))
+}
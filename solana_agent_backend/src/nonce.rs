@@ -0,0 +1,249 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::nonce_utils::nonblocking::data_from_account;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{Message, VersionedMessage},
+    nonce::State,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::{self, SystemInstruction},
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── DURABLE NONCE ACCOUNTS ──────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// A blockhash is only valid for ~60-90 seconds, which the Phantom deep-link
+// round-trip on mobile regularly exceeds. A durable nonce replaces the
+// blockhash with a value stored in an on-chain account: the transaction
+// stays valid until it lands or the nonce is advanced.
+//
+// Each user gets one nonce account, derived from their wallet with a fixed
+// seed, so no extra keypair ever has to be generated, stored or signed with.
+
+/// Seed for `create_account_with_seed`; max 32 bytes
+pub const NONCE_SEED: &str = "agent-durable-nonce";
+
+/// On-chain state of a user's nonce account
+pub struct NonceInfo {
+    pub address: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+    pub lamports: u64,
+}
+
+/// Deterministic nonce account address for a user
+pub fn nonce_address(user: &Pubkey) -> Result<Pubkey, String> {
+    Pubkey::create_with_seed(user, NONCE_SEED, &system_program::id())
+        .map_err(|e| format!("Failed to derive nonce address: {}", e))
+}
+
+/// Fetch the user's nonce account. `Ok(None)` means it hasn't been created yet.
+pub async fn fetch_nonce(rpc: &RpcClient, user: &str) -> Result<Option<NonceInfo>, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    let address = nonce_address(&user_pub)?;
+
+    let account = rpc.get_account_with_commitment(&address, rpc.commitment()).await
+        .map_err(|e| format!("Failed to fetch nonce account: {}", e))?
+        .value;
+
+    let account = match account {
+        Some(a) => a,
+        None => return Ok(None),
+    };
+
+    let data = data_from_account(&account)
+        .map_err(|e| format!("Nonce account {} is not usable: {}", address, e))?;
+
+    Ok(Some(NonceInfo {
+        address,
+        authority: data.authority,
+        blockhash: data.blockhash(),
+        lamports: account.lamports,
+    }))
+}
+
+/// Build the transaction that creates and funds the user's nonce account.
+/// The user pays the rent-exempt deposit and is the nonce authority.
+pub async fn build_create_nonce_tx(rpc: &RpcClient, user: &str) -> Result<String, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    let address = nonce_address(&user_pub)?;
+
+    let rent = rpc.get_minimum_balance_for_rent_exemption(State::size()).await
+        .map_err(|e| format!("Failed to fetch nonce rent: {}", e))?;
+
+    let instructions = system_instruction::create_nonce_account_with_seed(
+        &user_pub,
+        &address,
+        &user_pub,
+        NONCE_SEED,
+        &user_pub,
+        rent,
+    );
+
+    let msg = Message::new(&instructions, Some(&user_pub));
+    encode_tx(Transaction::new_unsigned(msg))
+}
+
+/// Build a transaction that only advances the nonce. Any outstanding
+/// transaction built on the current nonce value becomes permanently invalid,
+/// so this is how a pending durable transaction is cancelled.
+pub fn build_advance_nonce_tx(user: &str, info: &NonceInfo) -> Result<String, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+
+    let mut msg = Message::new_with_nonce(vec![], Some(&user_pub), &info.address, &info.authority);
+    msg.recent_blockhash = info.blockhash;
+    encode_tx(Transaction::new_unsigned(msg))
+}
+
+/// Build a transaction that withdraws everything from the nonce account back
+/// to the user, closing it. Uses a regular blockhash.
+pub fn build_close_nonce_tx(user: &str, info: &NonceInfo) -> Result<String, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+
+    let ix = system_instruction::withdraw_nonce_account(&info.address, &info.authority, &user_pub, info.lamports);
+    let msg = Message::new(&[ix], Some(&user_pub));
+    encode_tx(Transaction::new_unsigned(msg))
+}
+
+/// Rebuild an unsigned legacy transaction on top of a durable nonce:
+/// `advance_nonce_account` goes first and the nonce value replaces the blockhash.
+pub fn apply_durable_nonce(tx_base64: &str, info: &NonceInfo) -> Result<String, String> {
+    let tx_bytes = general_purpose::STANDARD.decode(tx_base64)
+        .map_err(|e| format!("Failed to decode tx: {}", e))?;
+
+    let tx: VersionedTransaction = bincode::deserialize(&tx_bytes)
+        .map_err(|e| format!("Failed to deserialize tx: {}", e))?;
+
+    // Builders that sign server-side (e.g. NFT mints) apply the nonce
+    // themselves; it has to be this user's nonce account
+    if uses_durable_nonce(&tx.message) {
+        let keys = tx.message.static_account_keys();
+        let account = |i: usize| tx.message.instructions()[0].accounts.get(i).and_then(|&k| keys.get(k as usize));
+        if account(0) != Some(&info.address) {
+            return Err(format!("Transaction advances a different nonce account than {}", info.address));
+        }
+        if account(2) != Some(&info.authority) {
+            return Err(format!("Transaction's nonce authority isn't {}", info.authority));
+        }
+        return Ok(tx_base64.to_string());
+    }
+
+    if tx.signatures.iter().any(|s| *s != Signature::default()) {
+        return Err("Cannot apply a durable nonce to an already-signed transaction".to_string());
+    }
+
+    let msg = match tx.message {
+        VersionedMessage::Legacy(msg) => msg,
+        VersionedMessage::V0(_) => {
+            return Err("Durable nonce mode is not available for versioned transactions (e.g. Jupiter swaps)".to_string());
+        }
+    };
+
+    let payer = *msg.account_keys.first().ok_or("Transaction has no fee payer")?;
+    let instructions = decompile_instructions(&msg);
+
+    let mut nonce_msg = Message::new_with_nonce(instructions, Some(&payer), &info.address, &info.authority);
+    nonce_msg.recent_blockhash = info.blockhash;
+
+    // The advance instruction and its accounts can push a packed transaction over the limit
+    let tx = Transaction::new_unsigned(nonce_msg);
+    let size = bincode::serialized_size(&tx).map_err(|e| format!("Serialize error: {}", e))? as usize;
+    if size > PACKET_DATA_SIZE {
+        return Err(format!(
+            "With the durable nonce the transaction is {} bytes, over the {}-byte limit. Retry without durable_nonce.",
+            size, PACKET_DATA_SIZE
+        ));
+    }
+    encode_tx(tx)
+}
+
+/// A durable nonce transaction starts with `advance_nonce_account` and never
+/// expires by block height, so the blockhash check doesn't apply.
+pub fn uses_durable_nonce(message: &VersionedMessage) -> bool {
+    message.instructions().first().is_some_and(|ix| {
+        message.static_account_keys().get(ix.program_id_index as usize) == Some(&system_program::id())
+            && matches!(bincode::deserialize(&ix.data), Ok(SystemInstruction::AdvanceNonceAccount))
+    })
+}
+
+/// Turn a compiled legacy message back into instructions so it can be recompiled
+fn decompile_instructions(msg: &Message) -> Vec<Instruction> {
+    msg.instructions.iter().map(|ci| Instruction {
+        program_id: msg.account_keys[ci.program_id_index as usize],
+        accounts: ci.accounts.iter().map(|&i| {
+            let i = i as usize;
+            let key = msg.account_keys[i];
+            if msg.is_writable(i) {
+                AccountMeta::new(key, msg.is_signer(i))
+            } else {
+                AccountMeta::new_readonly(key, msg.is_signer(i))
+            }
+        }).collect(),
+        data: ci.data.clone(),
+    }).collect()
+}
+
+fn encode_tx(tx: Transaction) -> Result<String, String> {
+    Ok(general_purpose::STANDARD.encode(
+        bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(address: Pubkey, authority: Pubkey) -> NonceInfo {
+        NonceInfo { address, authority, blockhash: Hash::new_unique(), lamports: 0 }
+    }
+
+    fn nonce_tx(payer: &Pubkey, address: &Pubkey, authority: &Pubkey) -> String {
+        let ix = system_instruction::transfer(payer, &Pubkey::new_unique(), 1);
+        encode_tx(Transaction::new_unsigned(Message::new_with_nonce(vec![ix], Some(payer), address, authority))).unwrap()
+    }
+
+    #[test]
+    fn keeps_a_tx_on_the_users_nonce() {
+        let user = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let tx = nonce_tx(&user, &address, &user);
+        assert_eq!(apply_durable_nonce(&tx, &info(address, user)), Ok(tx));
+    }
+
+    #[test]
+    fn rejects_a_tx_the_nonce_pushes_over_the_limit() {
+        let user = Pubkey::new_unique();
+        let info = info(Pubkey::new_unique(), user);
+        let tx_with = |data_len: usize| {
+            let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &vec![1; data_len], vec![AccountMeta::new(user, true)]);
+            encode_tx(Transaction::new_unsigned(Message::new(&[ix], Some(&user)))).unwrap()
+        };
+        // Largest plain transaction that fits a packet
+        let full = (0..PACKET_DATA_SIZE).rev()
+            .find(|&len| general_purpose::STANDARD.decode(tx_with(len)).unwrap().len() <= PACKET_DATA_SIZE)
+            .unwrap();
+        assert!(apply_durable_nonce(&tx_with(full), &info).is_err());
+        assert!(apply_durable_nonce(&tx_with(full - 200), &info).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_tx_on_another_nonce() {
+        let user = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let other = nonce_tx(&user, &Pubkey::new_unique(), &user);
+        assert!(apply_durable_nonce(&other, &info(address, user)).is_err());
+        let other_authority = nonce_tx(&user, &address, &Pubkey::new_unique());
+        assert!(apply_durable_nonce(&other_authority, &info(address, user)).is_err());
+    }
+}
@@ -0,0 +1,41 @@
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use std::env;
+use std::sync::Arc;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── OPERATOR KEYPAIR ────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// A few features need the backend itself to sign: it owns the compressed
+// NFT trees, for example. Users' funds never depend on this key.
+
+/// Load the operator keypair from OPERATOR_KEYPAIR (solana-keygen JSON byte
+/// array) or OPERATOR_KEYPAIR_PATH (keypair file). `None` disables the
+/// features that need it.
+pub fn load_from_env() -> Option<Arc<Keypair>> {
+    if let Ok(json) = env::var("OPERATOR_KEYPAIR") {
+        let bytes: Vec<u8> = match serde_json::from_str(json.trim()) {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("[OPERATOR] OPERATOR_KEYPAIR is not a JSON byte array: {}", e);
+                return None;
+            }
+        };
+        return match Keypair::from_bytes(&bytes) {
+            Ok(kp) => Some(Arc::new(kp)),
+            Err(e) => {
+                eprintln!("[OPERATOR] Invalid OPERATOR_KEYPAIR: {}", e);
+                None
+            }
+        };
+    }
+
+    let path = env::var("OPERATOR_KEYPAIR_PATH").ok()?;
+    match read_keypair_file(&path) {
+        Ok(kp) => Some(Arc::new(kp)),
+        Err(e) => {
+            eprintln!("[OPERATOR] Failed to read {}: {}", path, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::token::TOKEN_2022_ID;
+use crate::wsol;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── PORTFOLIO (READ-ONLY BALANCES) ──────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Native SOL plus every SPL Token and Token-2022 balance the wallet holds.
+// Balances in several accounts of the same mint are added up; NFTs (0
+// decimals, amount 1) are listed apart from fungible tokens.
+
+/// One fungible token, summed over all of the owner's accounts for its mint
+#[derive(Serialize, Debug, Clone)]
+pub struct Holding {
+    /// Registry symbol, when the mint is one we know
+    pub symbol: Option<String>,
+    pub mint: String,
+    pub program: &'static str,
+    pub amount: f64,
+    pub amount_atomic: u64,
+    pub decimals: u8,
+    pub accounts: usize,
+    pub usd_price: Option<f64>,
+    pub usd_value: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Portfolio {
+    pub owner: String,
+    pub lamports: u64,
+    pub sol: f64,
+    /// Known tokens first, then the rest by balance
+    pub tokens: Vec<Holding>,
+    /// Mints of NFTs held
+    pub nfts: Vec<String>,
+    /// Zero-balance accounts that CLOSE_EMPTY could reclaim rent from
+    pub empty_accounts: usize,
+    pub sol_usd_value: Option<f64>,
+    /// SOL plus every token with a price
+    pub total_usd: Option<f64>,
+    /// Holdings without a price, left out of `total_usd`
+    pub unpriced: usize,
+    /// Token programs whose accounts couldn't be read; their balances are missing
+    pub warnings: Vec<String>,
+}
+
+impl Portfolio {
+    /// The holding for a symbol or mint address, if the wallet has any
+    pub fn find(&self, token: &str) -> Option<&Holding> {
+        self.tokens.iter().find(|h| h.mint == token || h.symbol.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(token)))
+    }
+
+    /// Fill in USD values. `price_of` returns None for holdings it can't price.
+    pub fn value(&mut self, sol_price: Option<f64>, price_of: impl Fn(&Holding) -> Option<f64>) {
+        self.sol_usd_value = sol_price.map(|p| p * self.sol);
+        let mut total = self.sol_usd_value;
+        self.unpriced = 0;
+        for holding in &mut self.tokens {
+            holding.usd_price = price_of(holding);
+            holding.usd_value = holding.usd_price.map(|p| p * holding.amount);
+            match holding.usd_value {
+                Some(v) => total = Some(total.unwrap_or(0.0) + v),
+                None => self.unpriced += 1,
+            }
+        }
+        self.total_usd = total;
+    }
+}
+
+/// Read the wallet's balances. `symbol_for` names a mint, so test networks
+/// can map the faucet's mints as well as the registry's.
+pub async fn fetch_portfolio(
+    rpc: &RpcClient,
+    owner: &str,
+    symbol_for: impl Fn(&str) -> Option<String>,
+) -> Result<Portfolio, String> {
+    let owner_pub = Pubkey::from_str(owner)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    let lamports = rpc.get_balance(&owner_pub).await
+        .map_err(|e| format!("Failed to fetch SOL balance: {}", e))?;
+
+    let wsol_mint = wsol::wsol_mint().to_string();
+    let mut holdings: BTreeMap<String, Holding> = BTreeMap::new();
+    let mut nfts = vec![];
+    let mut empty_accounts = 0;
+    let mut warnings = vec![];
+
+    for (program, label) in [(spl_token::id(), "spl-token"), (TOKEN_2022_ID, "token-2022")] {
+        // One program failing still leaves SOL and the other program's balances worth showing
+        let keyed = match rpc.get_token_accounts_by_owner(&owner_pub, TokenAccountsFilter::ProgramId(program)).await {
+            Ok(k) => k,
+            Err(e) => {
+                println!("[PORTFOLIO] Failed to fetch {} accounts for {}: {}", label, owner, e);
+                warnings.push(format!("Couldn't read {} balances: {}", label, e));
+                continue;
+            },
+        };
+
+        for account in keyed {
+            let data = serde_json::to_value(&account.account.data).unwrap_or_default();
+            let info = &data["parsed"]["info"];
+            let mint = info["mint"].as_str().unwrap_or_default().to_string();
+            let amount_atomic: u64 = info["tokenAmount"]["amount"].as_str()
+                .and_then(|a| a.parse().ok())
+                .unwrap_or(0);
+            let decimals = info["tokenAmount"]["decimals"].as_u64().unwrap_or(0) as u8;
+
+            if amount_atomic == 0 {
+                empty_accounts += 1;
+                continue;
+            }
+            if decimals == 0 && amount_atomic == 1 {
+                nfts.push(mint);
+                continue;
+            }
+
+            let holding = holdings.entry(mint.clone()).or_insert_with(|| Holding {
+                // The registry's SOL mint is wrapped SOL, which isn't the native balance
+                symbol: if mint == wsol_mint { Some("wSOL".to_string()) } else { symbol_for(&mint) },
+                mint,
+                program: label,
+                amount: 0.0,
+                amount_atomic: 0,
+                decimals,
+                accounts: 0,
+                usd_price: None,
+                usd_value: None,
+            });
+            holding.amount_atomic += amount_atomic;
+            holding.amount = holding.amount_atomic as f64 / 10f64.powi(decimals as i32);
+            holding.accounts += 1;
+        }
+    }
+
+    let mut tokens: Vec<Holding> = holdings.into_values().collect();
+    tokens.sort_by(|a, b| b.symbol.is_some().cmp(&a.symbol.is_some()).then(b.amount.total_cmp(&a.amount)));
+
+    Ok(Portfolio {
+        owner: owner.to_string(),
+        lamports,
+        sol: lamports as f64 / 1_000_000_000.0,
+        tokens,
+        nfts,
+        empty_accounts,
+        sol_usd_value: None,
+        total_usd: None,
+        unpriced: 0,
+        warnings,
+    })
+}
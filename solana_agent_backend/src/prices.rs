@@ -0,0 +1,412 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::rpc;
+use crate::swap::{jupiter_api_key, token_mint, token_symbol};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── USD PRICES ──────────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Prices are keyed by mainnet mint. Each backend sits behind `PriceSource`;
+// `PriceCache` keeps answers for a short TTL and drops any price whose
+// publish time is older than the staleness limit, so a stuck oracle shows
+// "no price" rather than a wrong one. Valuation is best-effort: a missing
+// price never fails an intent.
+
+/// Re-fetch a cached price after this long
+const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// Ignore prices published longer ago than this
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(120);
+
+const JUPITER_PRICE_API: &str = "https://api.jup.ag/price/v3";
+
+/// Rough prices for the token registry, used by the static source and to
+/// seed test pools
+const REFERENCE_PRICES_USD: &[(&str, f64)] = &[
+    ("SOL", 150.0),
+    ("USDC", 1.0),
+    ("USDT", 1.0),
+    ("BONK", 0.00002),
+    ("JUP", 0.8),
+    ("RAY", 2.0),
+    ("WIF", 1.5),
+];
+
+/// Reference USD price of a registry symbol
+pub fn reference_price(symbol: &str) -> Option<f64> {
+    REFERENCE_PRICES_USD.iter().find(|(s, _)| *s == symbol).map(|(_, p)| *p)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Price {
+    pub usd: f64,
+    /// Unix seconds the source says the price is from
+    pub published_at: i64,
+    pub source: &'static str,
+}
+
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// USD prices for `mints`. Mints the source can't price are left out.
+    async fn prices(&self, mints: &[String]) -> Result<HashMap<String, Price>, String>;
+}
+
+/// Pick the backend from PRICE_SOURCE (jupiter | pyth | static). Defaults to jupiter.
+pub fn source_from_env() -> Box<dyn PriceSource> {
+    match env::var("PRICE_SOURCE").unwrap_or_default().to_lowercase().as_str() {
+        "pyth" => Box::new(PythPrices),
+        "static" => Box::new(StaticPrices::reference()),
+        _ => Box::new(JupiterPrices),
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// ─── JUPITER PRICE API ──────────────────────────────────────
+
+pub struct JupiterPrices;
+
+#[async_trait]
+impl PriceSource for JupiterPrices {
+    fn name(&self) -> &'static str { "jupiter" }
+
+    async fn prices(&self, mints: &[String]) -> Result<HashMap<String, Price>, String> {
+        let api_key = jupiter_api_key()?;
+        let res = Client::new()
+            .get(JUPITER_PRICE_API)
+            .query(&[("ids", mints.join(","))])
+            .header("x-api-key", &api_key)
+            .send().await
+            .map_err(|e| format!("Jupiter price request failed: {}", e))?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(format!("Jupiter price error ({}): {}", status, body));
+        }
+        let data: serde_json::Value = res.json().await
+            .map_err(|e| format!("Jupiter price parse error: {}", e))?;
+
+        // The API quotes live prices without a timestamp, so they're "now"
+        let fetched = now();
+        Ok(mints.iter()
+            .filter_map(|mint| {
+                let usd = data[mint.as_str()]["usdPrice"].as_f64()?;
+                Some((mint.clone(), Price { usd, published_at: fetched, source: self.name() }))
+            })
+            .collect())
+    }
+}
+
+// ─── PYTH (ON-CHAIN) ────────────────────────────────────────
+
+/// Pyth price feed ids (hex) for the registry. Override one with
+/// PYTH_FEED_<SYMBOL>=<feed id>.
+const PYTH_FEED_IDS: &[(&str, &str)] = &[
+    ("SOL", "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d"),
+    ("USDC", "eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a"),
+    ("USDT", "2b89b9dc8fdf9f34709a5b106b472f0f39bb6ca9ce04b0fd7f2e971688e2e53b"),
+    ("BONK", "72b021217ca3fe68922a19aaf990109cb9d84e9ad004b4d2025ad6f529314419"),
+    ("JUP", "0a0408d619e9380abad35060f9192039ed5042fa6f82301d0e48bb52be830996"),
+    ("RAY", "91568baa8beb53db23eb3fb7f22c6e8bd303d103919e19733f2bb642d3e7987a"),
+    ("WIF", "4ca4beeca86f0d164160323817a4e42b10010a724c2217c6ee41b54cd4cc61fc"),
+];
+
+/// Pyth push oracle; its shard-0 feed accounts are the sponsored ones kept updated
+const PYTH_PUSH_ORACLE: Pubkey = solana_sdk::pubkey!("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT");
+
+// PriceUpdateV2 layout: discriminator, write authority, verification level
+// (1 = Full, no payload), then the price message
+const PYTH_VERIFICATION_LEVEL: usize = 40;
+const PYTH_VERIFIED_FULL: u8 = 1;
+const PYTH_FEED_ID: usize = 41;
+const PYTH_PRICE: usize = 73;
+const PYTH_EXPONENT: usize = 89;
+const PYTH_PUBLISH_TIME: usize = 93;
+
+/// Reads prices from the Pyth push oracle's price update accounts
+pub struct PythPrices;
+
+impl PythPrices {
+    fn feed_for(symbol: &str) -> Option<[u8; 32]> {
+        let configured = env::var(format!("PYTH_FEED_{}", symbol)).ok();
+        let feed = configured.as_deref().or_else(|| {
+            PYTH_FEED_IDS.iter().find(|(s, _)| *s == symbol).map(|(_, f)| *f)
+        })?;
+        let feed = feed.trim().trim_start_matches("0x");
+        if feed.len() != 64 {
+            return None;
+        }
+        let mut id = [0u8; 32];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(feed.get(2 * i..2 * i + 2)?, 16).ok()?;
+        }
+        Some(id)
+    }
+
+    /// The shard-0 price feed account for a feed id
+    fn account_for(feed_id: &[u8; 32]) -> Pubkey {
+        Pubkey::find_program_address(&[&0u16.to_le_bytes(), feed_id], &PYTH_PUSH_ORACLE).0
+    }
+
+    /// (price, publish time) from a fully verified update for `feed_id`
+    fn parse(data: &[u8], feed_id: &[u8; 32]) -> Option<(f64, i64)> {
+        if data.get(..8)? != Sha256::digest(b"account:PriceUpdateV2").get(..8)? {
+            return None;
+        }
+        if *data.get(PYTH_VERIFICATION_LEVEL)? != PYTH_VERIFIED_FULL || data.get(PYTH_FEED_ID..PYTH_FEED_ID + 32)? != feed_id {
+            return None;
+        }
+        let i32_at = |o: usize| data.get(o..o + 4).map(|b| i32::from_le_bytes(b.try_into().unwrap()));
+        let i64_at = |o: usize| data.get(o..o + 8).map(|b| i64::from_le_bytes(b.try_into().unwrap()));
+        let price = i64_at(PYTH_PRICE)? as f64 * 10f64.powi(i32_at(PYTH_EXPONENT)?);
+        Some((price, i64_at(PYTH_PUBLISH_TIME)?))
+    }
+}
+
+#[async_trait]
+impl PriceSource for PythPrices {
+    fn name(&self) -> &'static str { "pyth" }
+
+    async fn prices(&self, mints: &[String]) -> Result<HashMap<String, Price>, String> {
+        let feeds: Vec<(&String, [u8; 32])> = mints.iter()
+            .filter_map(|mint| Some((mint, Self::feed_for(token_symbol(mint)?)?)))
+            .collect();
+        if feeds.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // Feed accounts live on mainnet whatever network the request is for
+        let accounts: Vec<Pubkey> = feeds.iter().map(|(_, feed)| Self::account_for(feed)).collect();
+        let fetched = rpc::client("mainnet").get_multiple_accounts(&accounts).await
+            .map_err(|e| format!("Failed to fetch Pyth price accounts: {}", e))?;
+
+        Ok(feeds.into_iter().zip(fetched)
+            .filter_map(|((mint, feed), account)| {
+                let (usd, published_at) = Self::parse(&account?.data, &feed)?;
+                Some((mint.clone(), Price { usd, published_at, source: self.name() }))
+            })
+            .collect())
+    }
+}
+
+// ─── STATIC ─────────────────────────────────────────────────
+
+/// Fixed prices that never go stale, for offline development and tests
+pub struct StaticPrices {
+    prices: HashMap<String, f64>,
+    age: Duration,
+}
+
+impl StaticPrices {
+    /// `prices` keyed by mint
+    pub fn new(prices: HashMap<String, f64>) -> Self {
+        StaticPrices { prices, age: Duration::ZERO }
+    }
+
+    /// Report every price as published `age` ago, to exercise staleness checks
+    #[cfg(test)]
+    pub fn published_ago(self, age: Duration) -> Self {
+        StaticPrices { age, ..self }
+    }
+
+    /// The registry's reference prices
+    pub fn reference() -> Self {
+        Self::new(REFERENCE_PRICES_USD.iter()
+            .filter_map(|(symbol, usd)| Some((token_mint(symbol)?.to_string(), *usd)))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl PriceSource for StaticPrices {
+    fn name(&self) -> &'static str { "static" }
+
+    async fn prices(&self, mints: &[String]) -> Result<HashMap<String, Price>, String> {
+        let published_at = now() - self.age.as_secs() as i64;
+        Ok(mints.iter()
+            .filter_map(|mint| {
+                let usd = *self.prices.get(mint)?;
+                Some((mint.clone(), Price { usd, published_at, source: self.name() }))
+            })
+            .collect())
+    }
+}
+
+// ─── CACHE ──────────────────────────────────────────────────
+
+/// A price source behind a TTL cache and a staleness check
+pub struct PriceCache {
+    source: Box<dyn PriceSource>,
+    ttl: Duration,
+    max_age: Duration,
+    entries: Mutex<HashMap<String, (Price, Instant)>>,
+}
+
+impl PriceCache {
+    pub fn new(source: Box<dyn PriceSource>, ttl: Duration, max_age: Duration) -> Self {
+        PriceCache { source, ttl, max_age, entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// PRICE_SOURCE with PRICE_TTL_SECS / PRICE_MAX_AGE_SECS, defaulting to 30s / 120s
+    pub fn from_env() -> Self {
+        let secs = |var: &str, default: Duration| env::var(var).ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(default);
+        Self::new(source_from_env(), secs("PRICE_TTL_SECS", DEFAULT_TTL), secs("PRICE_MAX_AGE_SECS", DEFAULT_MAX_AGE))
+    }
+
+    /// Fresh prices for whichever of `mints` can be priced
+    pub async fn prices(&self, mints: &[String]) -> HashMap<String, Price> {
+        let mut out = HashMap::new();
+        let mut missing = vec![];
+        {
+            let entries = self.entries.lock().unwrap();
+            for mint in mints {
+                match entries.get(mint) {
+                    Some((price, at)) if at.elapsed() < self.ttl => { out.insert(mint.clone(), price.clone()); },
+                    _ => if !missing.contains(mint) { missing.push(mint.clone()) },
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            match self.source.prices(&missing).await {
+                Ok(fetched) => {
+                    let mut entries = self.entries.lock().unwrap();
+                    for (mint, price) in fetched {
+                        entries.insert(mint.clone(), (price.clone(), Instant::now()));
+                        out.insert(mint, price);
+                    }
+                },
+                Err(e) => println!("[PRICES] {} lookup failed: {}", self.source.name(), e),
+            }
+        }
+
+        let oldest = now() - self.max_age.as_secs() as i64;
+        out.retain(|mint, price| {
+            let fresh = price.published_at >= oldest;
+            if !fresh {
+                println!("[PRICES] Ignoring stale {} price for {} from {}", price.source, mint, price.published_at);
+            }
+            fresh
+        });
+        out
+    }
+
+    /// Fresh price for one mint
+    pub async fn price(&self, mint: &str) -> Option<Price> {
+        self.prices(&[mint.to_string()]).await.remove(mint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(age: Duration, ttl: Duration) -> PriceCache {
+        let source = StaticPrices::new(["A", "B", "C"].iter().map(|m| (m.to_string(), 1.0)).collect()).published_ago(age);
+        PriceCache::new(Box::new(source), ttl, Duration::from_secs(120))
+    }
+
+    fn fetched_at(cache: &PriceCache, mint: &str) -> Option<Instant> {
+        cache.entries.lock().unwrap().get(mint).map(|(_, at)| *at)
+    }
+
+    #[tokio::test]
+    async fn cached_prices_skip_the_source_within_ttl() {
+        let cache = cache(Duration::ZERO, Duration::from_secs(60));
+        let mints = vec!["A".to_string(), "B".to_string()];
+        assert_eq!(cache.prices(&mints).await.len(), 2);
+        let first = fetched_at(&cache, "A").unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(cache.prices(&mints).await.len(), 2);
+        assert_eq!(fetched_at(&cache, "A"), Some(first));
+        // Only requested mints are looked up
+        assert!(fetched_at(&cache, "C").is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_refetched() {
+        let cache = cache(Duration::ZERO, Duration::ZERO);
+        cache.price("A").await.unwrap();
+        let first = fetched_at(&cache, "A").unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        cache.price("A").await.unwrap();
+        assert!(fetched_at(&cache, "A").unwrap() > first);
+    }
+
+    #[tokio::test]
+    async fn stale_prices_are_dropped() {
+        assert!(cache(Duration::from_secs(121), Duration::from_secs(60)).price("A").await.is_none());
+        assert!(cache(Duration::from_secs(100), Duration::from_secs(60)).price("A").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn unpriced_and_duplicate_mints() {
+        let cache = cache(Duration::ZERO, Duration::from_secs(60));
+        let prices = cache.prices(&["A".to_string(), "A".to_string(), "Z".to_string()]).await;
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices["A"].source, "static");
+    }
+
+    fn update_account(feed_id: &[u8; 32], level: u8, expo: i32, price: i64, publish_time: i64) -> Vec<u8> {
+        let mut data = Sha256::digest(b"account:PriceUpdateV2")[..8].to_vec();
+        data.extend_from_slice(&[9; 32]);
+        data.push(level);
+        data.extend_from_slice(feed_id);
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&12u64.to_le_bytes());
+        data.extend_from_slice(&expo.to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&[0; 40]);
+        data
+    }
+
+    #[test]
+    fn pyth_feed_accounts() {
+        let sol = PythPrices::feed_for("SOL").unwrap();
+        assert_eq!(PythPrices::account_for(&sol).to_string(), "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
+        let usdc = PythPrices::feed_for("USDC").unwrap();
+        assert_eq!(PythPrices::account_for(&usdc).to_string(), "Dpw1EAVrSB1ibxiDQyTAW6Zip3J4Btk2x4SgApQCeFbX");
+        assert!(PythPrices::feed_for("NOPE").is_none());
+    }
+
+    #[test]
+    fn pyth_parse_reads_price_expo_and_publish_time() {
+        let feed = PythPrices::feed_for("SOL").unwrap();
+        let data = update_account(&feed, PYTH_VERIFIED_FULL, -8, 15_012_345_678, 1_760_000_000);
+        let (price, at) = PythPrices::parse(&data, &feed).unwrap();
+        assert!((price - 150.12345678).abs() < 1e-9);
+        assert_eq!(at, 1_760_000_000);
+    }
+
+    #[test]
+    fn pyth_parse_rejects_bad_accounts() {
+        let feed = PythPrices::feed_for("SOL").unwrap();
+        let good = update_account(&feed, PYTH_VERIFIED_FULL, -8, 1, 1);
+        // Another feed's update
+        assert!(PythPrices::parse(&good, &PythPrices::feed_for("USDC").unwrap()).is_none());
+        // Only partially verified
+        assert!(PythPrices::parse(&update_account(&feed, 0, -8, 1, 1), &feed).is_none());
+        // Not a price update account
+        let mut data = good.clone();
+        data[0] ^= 1;
+        assert!(PythPrices::parse(&data, &feed).is_none());
+        // Too short to hold the message
+        assert!(PythPrices::parse(&good[..PYTH_PUBLISH_TIME], &feed).is_none());
+    }
+}
@@ -0,0 +1,55 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::env;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── RPC ENDPOINTS ───────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Networks with a faucet, where test SOL can be requested
+pub const TEST_NETWORKS: &[&str] = &["devnet", "testnet", "localnet"];
+
+/// Resolve the JSON-RPC endpoint for the `network` field of a request.
+/// Public endpoints by default; set SOLANA_RPC_MAINNET / SOLANA_RPC_DEVNET /
+/// SOLANA_RPC_TESTNET / SOLANA_RPC_LOCALNET in .env to point elsewhere.
+/// Unknown networks fall back to devnet.
+pub fn rpc_url(network: &str) -> String {
+    match network {
+        "mainnet" => env::var("SOLANA_RPC_MAINNET")
+            .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
+        "testnet" => env::var("SOLANA_RPC_TESTNET")
+            .unwrap_or_else(|_| "https://api.testnet.solana.com".to_string()),
+        "localnet" => env::var("SOLANA_RPC_LOCALNET")
+            .unwrap_or_else(|_| "http://127.0.0.1:8899".to_string()),
+        _ => env::var("SOLANA_RPC_DEVNET")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string()),
+    }
+}
+
+/// Async RPC client for a network, reading at `confirmed` commitment
+pub fn client(network: &str) -> RpcClient {
+    RpcClient::new_with_commitment(rpc_url(network), CommitmentConfig::confirmed())
+}
+
+/// WebSocket (pubsub) endpoint for a network. Derived from the HTTP endpoint
+/// unless SOLANA_WS_<NETWORK> is set, since some providers serve websockets
+/// on a different host or port.
+pub fn ws_url(network: &str) -> String {
+    let var = match network {
+        "mainnet" => "SOLANA_WS_MAINNET",
+        "testnet" => "SOLANA_WS_TESTNET",
+        // solana-test-validator serves pubsub on the RPC port + 1
+        "localnet" => return env::var("SOLANA_WS_LOCALNET").unwrap_or_else(|_| "ws://127.0.0.1:8900".to_string()),
+        _ => "SOLANA_WS_DEVNET",
+    };
+    env::var(var).unwrap_or_else(|_| {
+        let http = rpc_url(network);
+        if let Some(rest) = http.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = http.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            http
+        }
+    })
+}
@@ -0,0 +1,572 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::ai::Intent;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── RECURRING PAYMENTS & DCA ────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Users register an intent with a cadence ("every Monday", "monthly").
+// We never hold keys, so a due run only pre-builds the transaction, parks
+// it on the schedule as `pending` and pings the user's webhook; the user
+// signs it from the app. An unsigned run is replaced by the next one, so a
+// missed payment never doubles up.
+
+/// Actions that may run on a schedule
+pub const SCHEDULABLE_ACTIONS: &[&str] = &["SWAP", "TRANSFER", "SPLIT_PAYMENT"];
+
+/// How often the runner looks for due schedules
+pub const TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Schedules one wallet may keep
+pub const MAX_SCHEDULES_PER_USER: usize = 20;
+
+/// Schedules stored across all wallets
+pub const MAX_SCHEDULES: usize = 10_000;
+
+/// When a schedule repeats. Runs keep the time of day of the first run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "every", rename_all = "snake_case")]
+pub enum Cadence {
+    Hours { n: u32 },
+    Days { n: u32 },
+    /// 0 = Monday
+    Weekly { weekday: u32 },
+    /// Clamped to the last day in shorter months
+    Monthly { day: u32 },
+}
+
+const WEEKDAYS: &[(&str, Weekday)] = &[
+    ("monday", Weekday::Mon), ("tuesday", Weekday::Tue), ("wednesday", Weekday::Wed), ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri), ("saturday", Weekday::Sat), ("sunday", Weekday::Sun),
+];
+
+/// Longest interval a schedule may repeat on
+const MAX_INTERVAL_DAYS: u32 = 3660;
+
+/// A whole word naming `day`: "mon", "tues", "monday", "mondays"...
+fn names_weekday(word: &str, day: &str) -> bool {
+    word.len() >= 3 && (day.starts_with(word) || word.strip_suffix('s') == Some(day))
+}
+
+impl Cadence {
+    /// Read what the user said ("every monday", "monthly on the 15th",
+    /// "every 6 hours", "daily"). `now` fills in an unstated weekday or day.
+    pub fn parse(text: &str, now: DateTime<Utc>) -> Result<Self, String> {
+        let text = text.trim().to_lowercase();
+        let number: Option<u32> = text.split(|c: char| !c.is_ascii_digit())
+            .find(|s| !s.is_empty())
+            .and_then(|s| s.parse().ok());
+
+        let cadence = if let Some((_, day)) = WEEKDAYS.iter().find(|(name, _)| {
+            text.split(|c: char| !c.is_alphabetic()).any(|w| names_weekday(w, name))
+        }) {
+            Cadence::Weekly { weekday: day.num_days_from_monday() }
+        } else if text.contains("month") {
+            Cadence::Monthly { day: month_day(&text)?.unwrap_or(now.day()) }
+        } else if text.contains("week") {
+            let n = number.unwrap_or(1).checked_mul(7)
+                .ok_or_else(|| format!("Every {} weeks is too long", number.unwrap_or(1)))?;
+            Cadence::Days { n }
+        } else if text.contains("hour") {
+            Cadence::Hours { n: number.unwrap_or(1) }
+        } else if text.contains("day") || text.contains("daily") {
+            Cadence::Days { n: number.unwrap_or(1) }
+        } else {
+            return Err(format!("Can't tell how often '{}' repeats. Try daily, weekly, every Monday or monthly.", text));
+        };
+
+        match cadence {
+            Cadence::Hours { n: 0 } | Cadence::Days { n: 0 } => Err("The interval must be at least 1".to_string()),
+            Cadence::Hours { n } if n / 24 > MAX_INTERVAL_DAYS => Err(format!("The interval can be at most {} days", MAX_INTERVAL_DAYS)),
+            Cadence::Days { n } if n > MAX_INTERVAL_DAYS => Err(format!("The interval can be at most {} days", MAX_INTERVAL_DAYS)),
+            Cadence::Monthly { day } if !(1..=31).contains(&day) => Err(format!("There is no day {} in a month", day)),
+            c => Ok(c),
+        }
+    }
+
+    /// The first run strictly after `from`
+    pub fn next_after(&self, from: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Cadence::Hours { n } => from + Duration::hours(*n as i64),
+            Cadence::Days { n } => from + Duration::days(*n as i64),
+            Cadence::Weekly { weekday } => {
+                let ahead = (*weekday as i64 - from.weekday().num_days_from_monday() as i64).rem_euclid(7);
+                from + Duration::days(if ahead == 0 { 7 } else { ahead })
+            },
+            Cadence::Monthly { day } => {
+                let this_month = on_day(from, from.year(), from.month(), *day);
+                if this_month > from {
+                    return this_month;
+                }
+                let (year, month) = if from.month() == 12 { (from.year() + 1, 1) } else { (from.year(), from.month() + 1) };
+                on_day(from, year, month, *day)
+            },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Cadence::Hours { n: 1 } => "every hour".to_string(),
+            Cadence::Hours { n } => format!("every {} hours", n),
+            Cadence::Days { n: 1 } => "every day".to_string(),
+            Cadence::Days { n: 7 } => "every week".to_string(),
+            Cadence::Days { n } => format!("every {} days", n),
+            Cadence::Weekly { weekday } => format!("every {}", Weekday::try_from(*weekday as u8).map(|d| d.to_string()).unwrap_or_default()),
+            Cadence::Monthly { day } => format!("monthly on day {}", day),
+        }
+    }
+}
+
+/// The day of a monthly cadence: an ordinal ("15th") or a number after
+/// "on (the)" / "day". Other numbers ("at 9am") aren't days. Only every
+/// month is supported, so "every 2 months" is refused rather than run monthly.
+fn month_day(text: &str) -> Result<Option<u32>, String> {
+    let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    let mut day = None;
+    for (i, word) in words.iter().enumerate() {
+        let digits = word.trim_end_matches(char::is_alphabetic);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(n) = digits.parse::<u32>() else { continue };
+        let suffix = &word[digits.len()..];
+        if suffix.is_empty() && words.get(i + 1).is_some_and(|w| w.starts_with("month")) {
+            if n != 1 {
+                return Err(format!("Only monthly schedules are supported, not every {} months", n));
+            }
+            continue;
+        }
+        let ordinal = matches!(suffix, "st" | "nd" | "rd" | "th");
+        let after_on = i > 0 && (matches!(words[i - 1], "on" | "day")
+            || (words[i - 1] == "the" && i > 1 && words[i - 2] == "on"));
+        if day.is_none() && (ordinal || (suffix.is_empty() && after_on)) {
+            day = Some(n);
+        }
+    }
+    Ok(day)
+}
+
+/// `at`'s time of day on `day` of the month, clamped to the month's length
+fn on_day(at: DateTime<Utc>, year: i32, month: u32, day: u32) -> DateTime<Utc> {
+    let date = (1..=day).rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .expect("every month has a first day");
+    date.and_time(at.time()).and_utc()
+}
+
+/// A built run waiting for the user's signature
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingRun {
+    pub due_at: DateTime<Utc>,
+    pub built_at: DateTime<Utc>,
+    /// The same body `/agent/execute` would have returned
+    pub response: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Schedule {
+    pub id: String,
+    pub user_pubkey: String,
+    pub network: String,
+    /// Build runs on the user's durable nonce so they stay signable until handled
+    pub durable_nonce: bool,
+    /// What the user asked for, as typed
+    pub description: String,
+    pub intent: Intent,
+    pub cadence: Cadence,
+    pub active: bool,
+    pub next_run: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub runs: u64,
+    /// POSTed a JSON notice whenever a run is ready to sign
+    pub notify_url: Option<String>,
+    pub pending: Option<PendingRun>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Everything needed to register a schedule
+pub struct NewSchedule {
+    pub user_pubkey: String,
+    pub network: String,
+    pub durable_nonce: bool,
+    pub description: String,
+    pub intent: Intent,
+    /// Cadence as the user phrased it
+    pub cadence: String,
+    pub notify_url: Option<String>,
+}
+
+/// Changes accepted by `PATCH /schedules/:id`
+#[derive(Deserialize, Debug, Default)]
+pub struct ScheduleUpdate {
+    pub active: Option<bool>,
+    pub cadence: Option<String>,
+    /// Empty string clears the webhook
+    pub notify_url: Option<String>,
+}
+
+/// Registered schedules, stored as JSON in DATA_DIR/schedules.json
+pub struct ScheduleStore {
+    path: PathBuf,
+    schedules: Mutex<Vec<Schedule>>,
+}
+
+impl ScheduleStore {
+    pub fn load(path: PathBuf) -> Self {
+        let schedules = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        ScheduleStore { path, schedules: Mutex::new(schedules) }
+    }
+
+    fn save(&self, schedules: &[Schedule]) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+        }
+        let json = serde_json::to_vec_pretty(schedules).map_err(|e| format!("Serialize error: {}", e))?;
+        std::fs::write(&self.path, json).map_err(|e| format!("Failed to save schedules: {}", e))
+    }
+
+    pub fn list(&self, user_pubkey: &str) -> Vec<Schedule> {
+        self.schedules.lock().unwrap().iter().filter(|s| s.user_pubkey == user_pubkey).cloned().collect()
+    }
+
+    /// The schedule, if it belongs to `user_pubkey`
+    pub fn get(&self, id: &str, user_pubkey: &str) -> Option<Schedule> {
+        self.schedules.lock().unwrap().iter().find(|s| s.id == id && s.user_pubkey == user_pubkey).cloned()
+    }
+
+    /// Validate and register a new schedule; the first run is the next
+    /// occurrence after now
+    pub fn create(&self, new: NewSchedule) -> Result<Schedule, String> {
+        if !SCHEDULABLE_ACTIONS.contains(&new.intent.action.as_str()) {
+            return Err(format!("{} can't be scheduled. Supported: {}", new.intent.action, SCHEDULABLE_ACTIONS.join(", ")));
+        }
+        validate_notify_url(new.notify_url.as_deref())?;
+        let now = Utc::now();
+        let cadence = Cadence::parse(&new.cadence, now)?;
+
+        let mut schedules = self.schedules.lock().unwrap();
+        if schedules.len() >= MAX_SCHEDULES {
+            return Err("The scheduler is full; try again later".to_string());
+        }
+        if schedules.iter().filter(|s| s.user_pubkey == new.user_pubkey).count() >= MAX_SCHEDULES_PER_USER {
+            return Err(format!("You already have {} schedules; delete one first", MAX_SCHEDULES_PER_USER));
+        }
+
+        let schedule = Schedule {
+            id: new_id(),
+            user_pubkey: new.user_pubkey,
+            network: new.network,
+            durable_nonce: new.durable_nonce,
+            description: new.description,
+            intent: new.intent,
+            next_run: cadence.next_after(now),
+            cadence,
+            active: true,
+            last_run: None,
+            runs: 0,
+            notify_url: new.notify_url.filter(|u| !u.trim().is_empty()),
+            pending: None,
+            created_at: now,
+        };
+        schedules.push(schedule.clone());
+        self.save(&schedules)?;
+        Ok(schedule)
+    }
+
+    pub fn update(&self, id: &str, user_pubkey: &str, update: ScheduleUpdate) -> Result<Schedule, String> {
+        let now = Utc::now();
+        let mut schedules = self.schedules.lock().unwrap();
+        let schedule = schedules.iter_mut().find(|s| s.id == id && s.user_pubkey == user_pubkey)
+            .ok_or_else(|| format!("No schedule {}", id))?;
+
+        if let Some(text) = update.cadence {
+            schedule.cadence = Cadence::parse(&text, now)?;
+            schedule.next_run = schedule.cadence.next_after(now);
+        }
+        if let Some(url) = update.notify_url {
+            validate_notify_url(Some(&url))?;
+            schedule.notify_url = Some(url).filter(|u| !u.trim().is_empty());
+        }
+        if let Some(active) = update.active {
+            // Resuming skips the runs missed while paused
+            if active && !schedule.active && schedule.next_run <= now {
+                schedule.next_run = schedule.cadence.next_after(now);
+            }
+            schedule.active = active;
+        }
+
+        let updated = schedule.clone();
+        self.save(&schedules)?;
+        Ok(updated)
+    }
+
+    pub fn delete(&self, id: &str, user_pubkey: &str) -> Result<(), String> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let before = schedules.len();
+        schedules.retain(|s| !(s.id == id && s.user_pubkey == user_pubkey));
+        if schedules.len() == before {
+            return Err(format!("No schedule {}", id));
+        }
+        self.save(&schedules)
+    }
+
+    /// Active schedules whose next run has come
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<Schedule> {
+        self.schedules.lock().unwrap().iter().filter(|s| s.active && s.next_run <= now).cloned().collect()
+    }
+
+    /// Park a built run on the schedule. `advance` moves `next_run` past now
+    /// (a scheduled run); a manual run leaves the cadence alone.
+    pub fn record_run(&self, id: &str, pending: Option<PendingRun>, advance: bool) -> Result<(), String> {
+        let now = Utc::now();
+        let mut schedules = self.schedules.lock().unwrap();
+        let Some(schedule) = schedules.iter_mut().find(|s| s.id == id) else {
+            // Deleted while the run was being built
+            return Ok(());
+        };
+        if advance {
+            let mut next = schedule.cadence.next_after(schedule.next_run);
+            while next <= now {
+                next = schedule.cadence.next_after(next);
+            }
+            schedule.next_run = next;
+            schedule.runs += 1;
+            schedule.last_run = Some(now);
+        }
+        schedule.pending = pending;
+        self.save(&schedules)
+    }
+}
+
+/// How long a signed ownership proof stays valid, either side of now
+const OWNER_PROOF_WINDOW_SECS: i64 = 300;
+
+/// What a wallet signs (as UTF-8, e.g. with `signMessage`) to manage its schedules
+pub fn owner_message(user_pubkey: &str, timestamp: i64) -> String {
+    format!("Manage schedules for {} at {}", user_pubkey, timestamp)
+}
+
+/// Check that `signature` (base58) is `user_pubkey` signing `owner_message`
+/// for a `timestamp` (unix seconds) close to `now`
+pub fn verify_owner(user_pubkey: &str, timestamp: i64, signature: &str, now: DateTime<Utc>) -> Result<(), String> {
+    if (now.timestamp() - timestamp).abs() > OWNER_PROOF_WINDOW_SECS {
+        return Err("Ownership proof expired; sign a fresh message".to_string());
+    }
+    let owner = Pubkey::from_str(user_pubkey)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    let signature = Signature::from_str(signature.trim())
+        .map_err(|e| format!("Invalid wallet signature: {}", e))?;
+    if !signature.verify(owner.as_ref(), owner_message(user_pubkey, timestamp).as_bytes()) {
+        return Err(format!("Signature doesn't match {}", user_pubkey));
+    }
+    Ok(())
+}
+
+fn validate_notify_url(url: Option<&str>) -> Result<(), String> {
+    match url.map(str::trim).filter(|u| !u.is_empty()) {
+        Some(u) if !u.starts_with("https://") => Err(format!("Invalid notify_url '{}': must be https", u)),
+        _ => Ok(()),
+    }
+}
+
+/// Resolve a notify_url's host and refuse any address inside the operator's
+/// network (loopback, private, link-local, metadata endpoints...). Returns
+/// the host and the addresses to pin the request to.
+pub async fn resolve_notify_url(url: &str) -> Result<(String, Vec<SocketAddr>), String> {
+    validate_notify_url(Some(url))?;
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| format!("Invalid notify_url '{}': {}", url, e))?;
+    let host = parsed.host_str()
+        .ok_or_else(|| format!("Invalid notify_url '{}': no host", url))?
+        .trim_start_matches('[').trim_end_matches(']')
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port)).await
+        .map_err(|e| format!("Couldn't resolve notify_url host '{}': {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("Couldn't resolve notify_url host '{}'", host));
+    }
+    if let Some(blocked) = addrs.iter().find(|a| !is_public(a.ip())) {
+        return Err(format!("notify_url host '{}' resolves to non-public address {}", host, blocked.ip()));
+    }
+    Ok((host, addrs))
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified()
+                || v4.is_broadcast() || v4.is_documentation() || v4.is_multicast()
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        },
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback() || v6.is_unspecified() || v6.is_multicast()
+                // Unique local fc00::/7 and link-local fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        },
+    }
+}
+
+fn new_id() -> String {
+    use rand::Rng;
+    let bytes: [u8; 12] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Tell the user a run is ready to sign. Best effort: a failing webhook
+/// doesn't stop the schedule.
+pub async fn notify(schedule: &Schedule, pending: &PendingRun) {
+    let Some(url) = schedule.notify_url.as_deref() else { return };
+    let body = serde_json::json!({
+        "event": "schedule_run_ready",
+        "schedule_id": schedule.id,
+        "description": schedule.description,
+        "user_pubkey": schedule.user_pubkey,
+        "network": schedule.network,
+        "pending": pending,
+    });
+    // Re-check at send time: DNS may have changed since the URL was saved.
+    // The request is pinned to the checked addresses and never redirected.
+    let (host, addrs) = match resolve_notify_url(url).await {
+        Ok(r) => r,
+        Err(e) => {
+            println!("[SCHEDULE] notify {} skipped: {}", schedule.id, e);
+            return;
+        },
+    };
+    let client = match reqwest::Client::builder()
+        .resolve_to_addrs(&host, &addrs)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            println!("[SCHEDULE] notify {} failed: {}", schedule.id, e);
+            return;
+        },
+    };
+    let sent = client
+        .post(url)
+        .timeout(std::time::Duration::from_secs(10))
+        .json(&body)
+        .send()
+        .await;
+    if let Err(e) = sent {
+        println!("[SCHEDULE] notify {} failed: {}", schedule.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse_weekdays_as_whole_words() {
+        let now = at(2026, 3, 4, 9);
+        for text in ["every monday", "Mondays", "mon", "each Mon."] {
+            assert_eq!(Cadence::parse(text, now), Ok(Cadence::Weekly { weekday: 0 }), "{}", text);
+        }
+        assert_eq!(Cadence::parse("every tues", now), Ok(Cadence::Weekly { weekday: 1 }));
+        assert_eq!(Cadence::parse("thurs", now), Ok(Cadence::Weekly { weekday: 3 }));
+        assert_eq!(Cadence::parse("sundays", now), Ok(Cadence::Weekly { weekday: 6 }));
+    }
+
+    #[test]
+    fn parse_monthly_is_not_monday() {
+        let now = at(2026, 3, 4, 9);
+        assert_eq!(Cadence::parse("monthly", now), Ok(Cadence::Monthly { day: 4 }));
+        assert_eq!(Cadence::parse("monthly on the 15th", now), Ok(Cadence::Monthly { day: 15 }));
+        assert_eq!(Cadence::parse("every month", now), Ok(Cadence::Monthly { day: 4 }));
+        assert!(Cadence::parse("monthly on the 32nd", now).is_err());
+    }
+
+    #[test]
+    fn parse_monthly_day_only_from_an_ordinal() {
+        let now = at(2026, 3, 4, 9);
+        assert_eq!(Cadence::parse("at 9am monthly on the 15th", now), Ok(Cadence::Monthly { day: 15 }));
+        assert_eq!(Cadence::parse("monthly at 10:30", now), Ok(Cadence::Monthly { day: 4 }));
+        assert_eq!(Cadence::parse("monthly on the 1st at 8", now), Ok(Cadence::Monthly { day: 1 }));
+        assert_eq!(Cadence::parse("on day 20 every month", now), Ok(Cadence::Monthly { day: 20 }));
+        assert_eq!(Cadence::parse("every month on 3", now), Ok(Cadence::Monthly { day: 3 }));
+    }
+
+    #[test]
+    fn parse_rejects_every_n_months() {
+        let now = at(2026, 3, 4, 9);
+        assert!(Cadence::parse("every 2 months", now).is_err());
+        assert!(Cadence::parse("every 3 months on the 15th", now).is_err());
+        assert_eq!(Cadence::parse("every 1 month", now), Ok(Cadence::Monthly { day: 4 }));
+    }
+
+    #[test]
+    fn parse_intervals() {
+        let now = at(2026, 3, 4, 9);
+        assert_eq!(Cadence::parse("daily", now), Ok(Cadence::Days { n: 1 }));
+        assert_eq!(Cadence::parse("every 3 days", now), Ok(Cadence::Days { n: 3 }));
+        assert_eq!(Cadence::parse("weekly", now), Ok(Cadence::Days { n: 7 }));
+        assert_eq!(Cadence::parse("every 2 weeks", now), Ok(Cadence::Days { n: 14 }));
+        assert_eq!(Cadence::parse("every 6 hours", now), Ok(Cadence::Hours { n: 6 }));
+        assert!(Cadence::parse("every 0 hours", now).is_err());
+        assert!(Cadence::parse("sometimes", now).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_huge_intervals() {
+        let now = at(2026, 3, 4, 9);
+        assert!(Cadence::parse("every 4294967295 weeks", now).is_err());
+        assert!(Cadence::parse("every 700000000 weeks", now).is_err());
+        assert!(Cadence::parse("every 4000000000 days", now).is_err());
+        assert!(Cadence::parse("every 4000000000 hours", now).is_err());
+    }
+
+    #[test]
+    fn next_after_intervals() {
+        let from = at(2026, 3, 4, 9);
+        assert_eq!(Cadence::Hours { n: 6 }.next_after(from), at(2026, 3, 4, 15));
+        assert_eq!(Cadence::Days { n: 30 }.next_after(from), at(2026, 4, 3, 9));
+    }
+
+    #[test]
+    fn next_after_weekly() {
+        // 2026-03-04 is a Wednesday
+        let from = at(2026, 3, 4, 9);
+        assert_eq!(Cadence::Weekly { weekday: 4 }.next_after(from), at(2026, 3, 6, 9));
+        assert_eq!(Cadence::Weekly { weekday: 0 }.next_after(from), at(2026, 3, 9, 9));
+        // The same weekday is a week out, never now
+        assert_eq!(Cadence::Weekly { weekday: 2 }.next_after(from), at(2026, 3, 11, 9));
+    }
+
+    #[test]
+    fn next_after_monthly() {
+        let from = at(2026, 3, 4, 9);
+        assert_eq!(Cadence::Monthly { day: 15 }.next_after(from), at(2026, 3, 15, 9));
+        assert_eq!(Cadence::Monthly { day: 4 }.next_after(from), at(2026, 4, 4, 9));
+        // Short months clamp to their last day
+        assert_eq!(Cadence::Monthly { day: 31 }.next_after(at(2026, 1, 31, 9)), at(2026, 2, 28, 9));
+        assert_eq!(Cadence::Monthly { day: 10 }.next_after(at(2026, 12, 20, 9)), at(2027, 1, 10, 9));
+    }
+}
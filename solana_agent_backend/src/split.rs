@@ -0,0 +1,202 @@
+use serde::Serialize;
+use solana_sdk::{
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::collections::HashSet;
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::swap;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── SPLIT PAYMENTS ──────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// "Split 30 USDC between A, B and C" or "pay 60/40 to X and Y". The total is
+// divided in atomic units with the largest-remainder method, so the shares
+// always add up to exactly the total, and every transfer goes in one
+// transaction.
+
+/// Weights are scaled to integers with this precision before dividing
+const WEIGHT_SCALE: f64 = 1_000_000.0;
+
+/// One recipient's part of the split
+#[derive(Serialize, Debug)]
+pub struct Share {
+    pub recipient: String,
+    /// Percent of the total this recipient was allotted
+    pub percent: f64,
+    pub amount: f64,
+    pub amount_atomic: u64,
+}
+
+pub struct SplitPlan {
+    pub tx_base64: String,
+    pub shares: Vec<Share>,
+    pub total_atomic: u64,
+    pub decimals: u8,
+}
+
+/// Divide `total` in proportion to `weights`. Each share gets its floor, then
+/// the leftover units go one each to the largest fractional remainders
+/// (earlier recipients win ties), so the result always sums to `total`.
+pub fn allocate(total: u64, weights: &[u64]) -> Vec<u64> {
+    let weight_sum: u128 = weights.iter().map(|w| *w as u128).sum();
+    if weight_sum == 0 {
+        return vec![0; weights.len()];
+    }
+
+    let mut shares: Vec<u64> = Vec::with_capacity(weights.len());
+    let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(weights.len());
+    for (i, w) in weights.iter().enumerate() {
+        let scaled = total as u128 * *w as u128;
+        shares.push((scaled / weight_sum) as u64);
+        remainders.push((scaled % weight_sum, i));
+    }
+
+    let leftover = total - shares.iter().sum::<u64>();
+    remainders.sort_by_key(|(r, i)| (std::cmp::Reverse(*r), *i));
+    for (_, i) in remainders.into_iter().take(leftover as usize) {
+        shares[i] += 1;
+    }
+    shares
+}
+
+/// Build one transaction paying `total` of `token` (SOL or a registry symbol)
+/// to `recipients`, equally or by `weights`
+pub fn build_split_tx(
+    sender: &str,
+    token: &str,
+    total: f64,
+    recipients: &[String],
+    weights: &[f64],
+    memo: Option<&str>,
+) -> Result<SplitPlan, String> {
+    let sender_pub = Pubkey::from_str(sender)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    if recipients.len() < 2 {
+        return Err("A split needs at least two recipients; use a normal transfer for one".to_string());
+    }
+    if total <= 0.0 {
+        return Err("Amount to split must be greater than 0".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    let recipient_keys = recipients.iter().map(|r| {
+        let key = Pubkey::from_str(r.trim()).map_err(|_| format!("Invalid recipient address '{}'", r))?;
+        if !seen.insert(key) {
+            return Err(format!("{} is listed twice; give each recipient once with its share", key));
+        }
+        Ok(key)
+    }).collect::<Result<Vec<_>, String>>()?;
+
+    let weights = if weights.is_empty() {
+        vec![1.0; recipients.len()]
+    } else if weights.len() != recipients.len() {
+        return Err(format!("Got {} shares for {} recipients", weights.len(), recipients.len()));
+    } else {
+        weights.to_vec()
+    };
+    if weights.iter().any(|w| !(*w > 0.0 && w.is_finite())) {
+        return Err("Every share must be greater than 0".to_string());
+    }
+    let scaled: Vec<u64> = weights.iter().map(|w| (w * WEIGHT_SCALE).round() as u64).collect();
+    let weight_sum: u64 = scaled.iter().sum();
+
+    let is_sol = token == "SOL";
+    let mint = if is_sol {
+        None
+    } else {
+        let mint = swap::token_mint(token)
+            .ok_or_else(|| format!("Unknown token '{}'. Supported: SOL, USDC, USDT, BONK, JUP, RAY, WIF", token))?;
+        Some(Pubkey::from_str(mint).map_err(|e| format!("Invalid mint address: {}", e))?)
+    };
+    let decimals = swap::token_decimals(token);
+    let total_atomic = (total * 10f64.powi(decimals as i32)).round() as u64;
+
+    let amounts = allocate(total_atomic, &scaled);
+    if amounts.contains(&0) {
+        return Err(format!("{} {} is too small to split {} ways", total, token, recipients.len()));
+    }
+
+    let mut instructions = vec![];
+    let mut shares = Vec::with_capacity(recipients.len());
+    for ((recipient, amount_atomic), weight) in recipient_keys.iter().zip(&amounts).zip(&scaled) {
+        match mint {
+            Some(mint) => instructions.extend(swap::transfer_spl_ixs(&sender_pub, recipient, &mint, *amount_atomic)?),
+            None => instructions.push(swap::transfer_sol_ix(&sender_pub, recipient, *amount_atomic)),
+        }
+        shares.push(Share {
+            recipient: recipient.to_string(),
+            percent: *weight as f64 * 100.0 / weight_sum as f64,
+            amount: *amount_atomic as f64 / 10f64.powi(decimals as i32),
+            amount_atomic: *amount_atomic,
+        });
+    }
+    if let Some(memo) = memo {
+        swap::validate_memo(memo)?;
+        instructions.push(swap::memo_ix(memo, &sender_pub));
+    }
+
+    let tx = Transaction::new_unsigned(Message::new(&instructions, Some(&sender_pub)));
+    let bytes = bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?;
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(format!(
+            "Splitting between {} recipients doesn't fit in one transaction; use a batch transfer instead",
+            recipients.len()
+        ));
+    }
+
+    Ok(SplitPlan {
+        tx_base64: general_purpose::STANDARD.encode(bytes),
+        shares,
+        total_atomic,
+        decimals,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate_sums_to_total() {
+        for (total, weights) in [
+            (100, vec![1, 1, 1]),
+            (1_000_000_007, vec![60, 40]),
+            (7, vec![3, 5, 11, 13]),
+            (u64::MAX, vec![u64::MAX, 1, 2]),
+            (0, vec![1, 2]),
+        ] {
+            assert_eq!(allocate(total, &weights).iter().sum::<u64>(), total, "{} / {:?}", total, weights);
+        }
+    }
+
+    #[test]
+    fn allocate_proportional_shares() {
+        assert_eq!(allocate(100, &[60, 40]), vec![60, 40]);
+        assert_eq!(allocate(10, &[1, 0, 4]), vec![2, 0, 8]);
+    }
+
+    #[test]
+    fn allocate_gives_leftover_to_largest_remainders() {
+        // 10 * (1, 2, 3) / 6 = 1.67, 3.33, 5.0 -> floors 1, 3, 5, one unit left for the first
+        assert_eq!(allocate(10, &[1, 2, 3]), vec![2, 3, 5]);
+    }
+
+    #[test]
+    fn allocate_ties_go_to_earlier_recipients() {
+        assert_eq!(allocate(100, &[1, 1, 1]), vec![34, 33, 33]);
+        assert_eq!(allocate(5, &[1, 1, 1, 1]), vec![2, 1, 1, 1]);
+        assert_eq!(allocate(2, &[1, 1, 1]), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn allocate_zero_weights() {
+        assert_eq!(allocate(100, &[0, 0]), vec![0, 0]);
+        assert!(allocate(100, &[]).is_empty());
+    }
+}
@@ -0,0 +1,403 @@
+use serde::Serialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    stake::{
+        self,
+        instruction::{self as stake_ix, create_account_with_seed_and_delegate_stake},
+        state::{Authorized, Lockup, StakeStateV2},
+    },
+    system_instruction,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── NATIVE STAKING ──────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Stake accounts are derived from the user's wallet with `stake:<n>` seeds,
+// like the durable nonce account, so no throwaway keypair has to sign.
+
+/// Seed prefix for stake accounts; the full seed is `stake:<n>`
+pub const STAKE_SEED_PREFIX: &str = "stake:";
+
+/// Seeds checked per getMultipleAccounts call when looking for a free one
+const SEED_BATCH: u64 = 100;
+
+/// Average slot time, for "epoch ends in" estimates
+const SLOT_MS: u64 = 400;
+
+/// A vote account that passed validation
+pub struct ValidatorInfo {
+    pub vote_account: Pubkey,
+    pub identity: String,
+    pub commission: u8,
+    pub activated_stake: u64,
+}
+
+/// A built create + delegate transaction
+pub struct StakeTx {
+    pub tx_base64: String,
+    pub stake_account: Pubkey,
+    pub seed: String,
+    pub lamports: u64,
+    pub rent_lamports: u64,
+    /// Epoch the delegation is recorded in; stake is active from the next one
+    pub activation_epoch: u64,
+    pub epoch_ends_in_secs: u64,
+}
+
+/// Check `vote_account` is a live, non-delinquent validator
+pub async fn validate_vote_account(rpc: &RpcClient, vote_account: &str) -> Result<ValidatorInfo, String> {
+    let vote_pub = Pubkey::from_str(vote_account)
+        .map_err(|e| format!("Invalid vote account: {}", e))?;
+
+    let status = rpc.get_vote_accounts().await
+        .map_err(|e| format!("Failed to fetch vote accounts: {}", e))?;
+
+    if status.delinquent.iter().any(|v| v.vote_pubkey == vote_account) {
+        return Err(format!("Validator {} is delinquent (not voting) - staking with it earns nothing", vote_account));
+    }
+
+    match status.current.into_iter().find(|v| v.vote_pubkey == vote_account) {
+        Some(v) => Ok(ValidatorInfo {
+            vote_account: vote_pub,
+            identity: v.node_pubkey,
+            commission: v.commission,
+            activated_stake: v.activated_stake,
+        }),
+        None => Err(format!("{} is not a vote account of an active validator", vote_account)),
+    }
+}
+
+/// Stake account for a given seed
+pub fn stake_address(user: &Pubkey, seed: &str) -> Result<Pubkey, String> {
+    Pubkey::create_with_seed(user, seed, &stake::program::id())
+        .map_err(|e| format!("Failed to derive stake address: {}", e))
+}
+
+/// First `stake:<n>` seed with no account behind it
+async fn next_free_seed(rpc: &RpcClient, user: &Pubkey) -> Result<(String, Pubkey), String> {
+    let mut start = 0u64;
+    loop {
+        let seeds: Vec<String> = (start..start + SEED_BATCH).map(|n| format!("{}{}", STAKE_SEED_PREFIX, n)).collect();
+        let addresses = seeds.iter().map(|s| stake_address(user, s)).collect::<Result<Vec<_>, _>>()?;
+        let accounts = rpc.get_multiple_accounts(&addresses).await
+            .map_err(|e| format!("Failed to check stake accounts: {}", e))?;
+
+        if let Some(i) = accounts.iter().position(Option::is_none) {
+            return Ok((seeds[i].clone(), addresses[i]));
+        }
+        start += SEED_BATCH;
+    }
+}
+
+/// Build `create_account_with_seed` + `initialize` + `delegate_stake`.
+/// The user funds the account and is both staker and withdrawer.
+pub async fn build_stake_tx(
+    rpc: &RpcClient,
+    user: &str,
+    validator: &ValidatorInfo,
+    amount_sol: f64,
+) -> Result<StakeTx, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+
+    if amount_sol <= 0.0 {
+        return Err("Stake amount must be greater than 0".to_string());
+    }
+    let stake_lamports = (amount_sol * 1_000_000_000.0) as u64;
+
+    let min_delegation = rpc.get_stake_minimum_delegation().await
+        .map_err(|e| format!("Failed to fetch minimum delegation: {}", e))?;
+    if stake_lamports < min_delegation {
+        return Err(format!("Minimum stake is {} SOL", min_delegation as f64 / 1_000_000_000.0));
+    }
+
+    let rent_lamports = rpc.get_minimum_balance_for_rent_exemption(StakeStateV2::size_of()).await
+        .map_err(|e| format!("Failed to fetch stake rent: {}", e))?;
+    let lamports = stake_lamports + rent_lamports;
+
+    let balance = rpc.get_balance(&user_pub).await
+        .map_err(|e| format!("Failed to fetch balance: {}", e))?;
+    if balance < lamports {
+        return Err(format!(
+            "Insufficient SOL: staking {} SOL needs {:.6} SOL including rent, wallet has {:.6}",
+            amount_sol,
+            lamports as f64 / 1_000_000_000.0,
+            balance as f64 / 1_000_000_000.0
+        ));
+    }
+
+    let (seed, stake_account) = next_free_seed(rpc, &user_pub).await?;
+
+    let instructions = create_account_with_seed_and_delegate_stake(
+        &user_pub,
+        &stake_account,
+        &user_pub,
+        &seed,
+        &validator.vote_account,
+        &Authorized { staker: user_pub, withdrawer: user_pub },
+        &Lockup::default(),
+        lamports,
+    );
+
+    let epoch = rpc.get_epoch_info().await
+        .map_err(|e| format!("Failed to fetch epoch info: {}", e))?;
+
+    Ok(StakeTx {
+        tx_base64: encode_tx(&instructions, &user_pub)?,
+        stake_account,
+        seed,
+        lamports,
+        rent_lamports,
+        activation_epoch: epoch.epoch,
+        epoch_ends_in_secs: epoch.slots_in_epoch.saturating_sub(epoch.slot_index) * SLOT_MS / 1000,
+    })
+}
+
+// ─── STAKE ACCOUNT MANAGEMENT ───────────────────────────────
+
+/// Byte offset of `Meta.authorized.staker` in a stake account
+/// (4-byte enum tag + 8-byte rent_exempt_reserve)
+const STAKER_OFFSET: usize = 12;
+
+/// Lifecycle of a stake account. Transitions are read per epoch; the
+/// network-wide warmup/cooldown cap can stretch them over a few more.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StakeStatus {
+    Activating,
+    Active,
+    Deactivating,
+    Inactive,
+}
+
+impl StakeStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StakeStatus::Activating => "activating",
+            StakeStatus::Active => "active",
+            StakeStatus::Deactivating => "deactivating",
+            StakeStatus::Inactive => "inactive",
+        }
+    }
+}
+
+/// A user's stake account, as reported in `meta`
+#[derive(Serialize, Debug, Clone)]
+pub struct StakeAccount {
+    #[serde(serialize_with = "as_string")]
+    pub address: Pubkey,
+    pub status: StakeStatus,
+    pub lamports: u64,
+    /// Delegated lamports (0 if never delegated)
+    pub delegated: u64,
+    pub rent_reserve: u64,
+    /// What `withdraw` can take right now
+    pub withdrawable: u64,
+    pub vote_account: Option<String>,
+    pub activation_epoch: Option<u64>,
+    pub deactivation_epoch: Option<u64>,
+    #[serde(skip)]
+    pub withdrawer: Pubkey,
+}
+
+fn as_string<S: serde::Serializer>(key: &Pubkey, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&key.to_string())
+}
+
+/// All stake accounts the user is staker of, oldest activation first
+pub async fn list_stake_accounts(rpc: &RpcClient, user: &Pubkey) -> Result<Vec<StakeAccount>, String> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(STAKER_OFFSET, user.as_ref()))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc.get_program_accounts_with_config(&stake::program::id(), config).await
+        .map_err(|e| format!("Failed to fetch stake accounts: {}", e))?;
+
+    let epoch = rpc.get_epoch_info().await
+        .map_err(|e| format!("Failed to fetch epoch info: {}", e))?
+        .epoch;
+
+    let mut list: Vec<StakeAccount> = accounts.into_iter()
+        .filter_map(|(address, account)| {
+            let state: StakeStateV2 = bincode::deserialize(&account.data).ok()?;
+            describe(address, account.lamports, &state, epoch)
+        })
+        .collect();
+    list.sort_by_key(|a| a.activation_epoch.unwrap_or(u64::MAX));
+    Ok(list)
+}
+
+/// Turn raw stake state into a `StakeAccount` at `epoch`
+fn describe(address: Pubkey, lamports: u64, state: &StakeStateV2, epoch: u64) -> Option<StakeAccount> {
+    match state {
+        StakeStateV2::Initialized(meta) => Some(StakeAccount {
+            address,
+            status: StakeStatus::Inactive,
+            lamports,
+            delegated: 0,
+            rent_reserve: meta.rent_exempt_reserve,
+            withdrawable: lamports,
+            vote_account: None,
+            activation_epoch: None,
+            deactivation_epoch: None,
+            withdrawer: meta.authorized.withdrawer,
+        }),
+        StakeStateV2::Stake(meta, stake, _) => {
+            let d = &stake.delegation;
+            let deactivating = d.deactivation_epoch != u64::MAX;
+            let status = if !deactivating {
+                // u64::MAX activation marks genesis (bootstrap) stake
+                if d.activation_epoch >= epoch && d.activation_epoch != u64::MAX {
+                    StakeStatus::Activating
+                } else {
+                    StakeStatus::Active
+                }
+            } else if d.deactivation_epoch >= epoch && d.activation_epoch != d.deactivation_epoch {
+                StakeStatus::Deactivating
+            } else {
+                StakeStatus::Inactive
+            };
+
+            let withdrawable = if status == StakeStatus::Inactive {
+                lamports
+            } else {
+                lamports.saturating_sub(meta.rent_exempt_reserve + d.stake)
+            };
+
+            Some(StakeAccount {
+                address,
+                status,
+                lamports,
+                delegated: d.stake,
+                rent_reserve: meta.rent_exempt_reserve,
+                withdrawable,
+                vote_account: Some(d.voter_pubkey.to_string()),
+                activation_epoch: Some(d.activation_epoch),
+                deactivation_epoch: deactivating.then_some(d.deactivation_epoch),
+                withdrawer: meta.authorized.withdrawer,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Pick the account the user meant: the named one, or the only one in an
+/// acceptable state when they didn't say.
+pub fn pick_stake_account<'a>(
+    accounts: &'a [StakeAccount],
+    address: Option<&str>,
+    allowed: &[StakeStatus],
+    what: &str,
+) -> Result<&'a StakeAccount, String> {
+    if let Some(address) = address {
+        let account = accounts.iter().find(|a| a.address.to_string() == address)
+            .ok_or_else(|| format!("{} is not one of your stake accounts", address))?;
+        if !allowed.contains(&account.status) {
+            return Err(format!("Can't {} stake account {}: it is {}", what, address, account.status.label()));
+        }
+        return Ok(account);
+    }
+
+    let candidates: Vec<&StakeAccount> = accounts.iter().filter(|a| allowed.contains(&a.status)).collect();
+    match candidates.as_slice() {
+        [only] => Ok(only),
+        [] => Err(format!("You have no stake account to {}", what)),
+        many => Err(format!(
+            "You have {} stake accounts that can {}, say which: {}",
+            many.len(),
+            what,
+            many.iter().map(|a| a.address.to_string()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// Start cooldown; the lamports become withdrawable once it completes
+pub fn build_deactivate_tx(user: &Pubkey, account: &StakeAccount) -> Result<String, String> {
+    encode_tx(&[stake_ix::deactivate_stake(&account.address, user)], user)
+}
+
+/// Withdraw `lamports` (default: everything withdrawable) back to the user.
+/// Withdrawing the full balance of an inactive account closes it.
+pub fn build_withdraw_tx(user: &Pubkey, account: &StakeAccount, lamports: Option<u64>) -> Result<(String, u64), String> {
+    if account.withdrawer != *user {
+        return Err(format!("You are not the withdraw authority of {}", account.address));
+    }
+    let amount = lamports.unwrap_or(account.withdrawable);
+    if amount == 0 {
+        return Err(format!("Nothing to withdraw from {} ({})", account.address, account.status.label()));
+    }
+    if amount > account.withdrawable {
+        return Err(format!(
+            "Only {} SOL is withdrawable from {} right now",
+            account.withdrawable as f64 / 1_000_000_000.0,
+            account.address
+        ));
+    }
+    let ix = stake_ix::withdraw(&account.address, user, user, amount, None);
+    Ok((encode_tx(&[ix], user)?, amount))
+}
+
+/// Merge `source` into `destination`. The stake program only allows it when
+/// both share a validator and are in compatible states.
+pub fn build_merge_tx(user: &Pubkey, destination: &StakeAccount, source: &StakeAccount) -> Result<String, String> {
+    if destination.address == source.address {
+        return Err("Can't merge a stake account into itself".to_string());
+    }
+    let both_delegated = destination.vote_account.is_some() && source.vote_account.is_some();
+    if both_delegated && destination.vote_account != source.vote_account {
+        return Err("Stake accounts delegated to different validators can't be merged".to_string());
+    }
+    if destination.status != source.status {
+        return Err(format!(
+            "Can't merge a {} account into a {} one",
+            source.status.label(),
+            destination.status.label()
+        ));
+    }
+    encode_tx(&stake_ix::merge(&destination.address, &source.address, user), user)
+}
+
+/// Split `lamports` off into a new seed-derived stake account. The new
+/// account is pre-funded with rent so the stake program accepts it.
+pub async fn build_split_tx(
+    rpc: &RpcClient,
+    user: &Pubkey,
+    account: &StakeAccount,
+    lamports: u64,
+) -> Result<(String, Pubkey), String> {
+    if lamports == 0 || lamports >= account.lamports.saturating_sub(account.rent_reserve) {
+        return Err(format!(
+            "Split amount must be between 0 and {} SOL",
+            account.lamports.saturating_sub(account.rent_reserve) as f64 / 1_000_000_000.0
+        ));
+    }
+
+    let (seed, new_account) = next_free_seed(rpc, user).await?;
+    let rent = rpc.get_minimum_balance_for_rent_exemption(StakeStateV2::size_of()).await
+        .map_err(|e| format!("Failed to fetch stake rent: {}", e))?;
+
+    let mut instructions = vec![system_instruction::transfer(user, &new_account, rent)];
+    instructions.extend(stake_ix::split_with_seed(&account.address, user, lamports, &new_account, user, &seed));
+    Ok((encode_tx(&instructions, user)?, new_account))
+}
+
+fn encode_tx(instructions: &[Instruction], payer: &Pubkey) -> Result<String, String> {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    Ok(general_purpose::STANDARD.encode(
+        bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+    ))
+}
@@ -0,0 +1,325 @@
+use futures::stream::{BoxStream, StreamExt};
+use solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcSignatureSubscribeConfig, RpcTransactionConfig};
+use solana_client::rpc_response::RpcSignatureResult;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use solana_transaction_status::{
+    TransactionConfirmationStatus, UiLoadedAddresses, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::{rpc, submit};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── TRANSACTION STATUS STREAM ───────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Stop watching if the signature hasn't finalized (or failed) by then
+const WATCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Most status streams watched at once, across all clients
+const MAX_STREAMS: usize = 256;
+
+/// Most status streams one client IP can hold open (see `client_ip` in main)
+const MAX_STREAMS_PER_IP: usize = 4;
+
+/// One server-sent event: (event name, JSON payload)
+pub type StatusEvent = (&'static str, serde_json::Value);
+
+/// Open stream counts, in total and per client IP. Each stream holds an RPC
+/// websocket, so the route is capped even though it sits outside the paywall.
+#[derive(Default)]
+pub struct StreamLimits {
+    open: Mutex<(usize, HashMap<IpAddr, usize>)>,
+}
+
+/// A counted stream; the count drops with it
+pub struct StreamSlot {
+    limits: Arc<StreamLimits>,
+    ip: IpAddr,
+}
+
+impl StreamLimits {
+    pub fn acquire(self: &Arc<Self>, ip: IpAddr) -> Result<StreamSlot, String> {
+        let mut open = self.open.lock().unwrap();
+        if open.0 >= MAX_STREAMS {
+            return Err("Too many status streams open; try again shortly".to_string());
+        }
+        let per_ip = open.1.entry(ip).or_insert(0);
+        if *per_ip >= MAX_STREAMS_PER_IP {
+            return Err(format!("At most {} status streams per client", MAX_STREAMS_PER_IP));
+        }
+        *per_ip += 1;
+        open.0 += 1;
+        Ok(StreamSlot { limits: self.clone(), ip })
+    }
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        let mut open = self.limits.open.lock().unwrap();
+        open.0 -= 1;
+        if let Some(n) = open.1.get_mut(&self.ip) {
+            *n -= 1;
+            if *n == 0 {
+                open.1.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Watch a signature over the RPC websocket and push every status change
+/// (processed → confirmed → finalized, or failed) into the returned channel.
+/// When `owner` is given, lamport changes on that wallet are pushed as
+/// `balance` events as well. The channel closes once the watch is over, and
+/// the watch stops early (releasing `slot`) if the receiver goes away.
+pub fn watch_signature(network: String, signature: Signature, owner: Option<Pubkey>, slot: StreamSlot) -> mpsc::Receiver<StatusEvent> {
+    let (tx, rx) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        let _slot = slot;
+        let result = tokio::select! {
+            r = tokio::time::timeout(WATCH_TIMEOUT, run_watch(&network, signature, owner, &tx)) => r,
+            _ = tx.closed() => return,
+        };
+        let end = match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => json!({ "signature": signature.to_string(), "message": e }),
+            Err(_) => json!({ "signature": signature.to_string(), "message": "Timed out waiting for finalization" }),
+        };
+        let _ = tx.send(("error", end)).await;
+    });
+
+    rx
+}
+
+async fn run_watch(
+    network: &str,
+    signature: Signature,
+    owner: Option<Pubkey>,
+    tx: &mpsc::Sender<StatusEvent>,
+) -> Result<(), String> {
+    let rpc = rpc::client(network);
+    let pubsub = PubsubClient::new(&rpc::ws_url(network)).await
+        .map_err(|e| format!("Websocket connect failed: {}", e))?;
+
+    // signatureSubscribe fires once at the requested commitment, so one
+    // subscription per stage gives us the whole progression.
+    let subscribe = |commitment: CommitmentConfig| pubsub.signature_subscribe(&signature, Some(RpcSignatureSubscribeConfig {
+        commitment: Some(commitment),
+        enable_received_notification: Some(false),
+    }));
+    let (mut processed, _unsub_processed) = subscribe(CommitmentConfig::processed()).await
+        .map_err(|e| format!("signatureSubscribe failed: {}", e))?;
+    let (mut confirmed, _unsub_confirmed) = subscribe(CommitmentConfig::confirmed()).await
+        .map_err(|e| format!("signatureSubscribe failed: {}", e))?;
+    let (mut finalized, _unsub_finalized) = subscribe(CommitmentConfig::finalized()).await
+        .map_err(|e| format!("signatureSubscribe failed: {}", e))?;
+
+    let mut last_lamports = None;
+    let mut balances: BoxStream<'_, u64> = match &owner {
+        Some(owner) => {
+            last_lamports = rpc.get_balance(owner).await.ok();
+            let (stream, _unsub) = pubsub.account_subscribe(owner, Some(RpcAccountInfoConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            })).await.map_err(|e| format!("accountSubscribe failed: {}", e))?;
+            stream.map(|update| update.value.lamports).boxed()
+        }
+        None => futures::stream::pending().boxed(),
+    };
+
+    // The signature may have landed before we subscribed - report where it is now
+    let mut stage = 0u8;
+    if let Ok(Some(Some(st))) = rpc.get_signature_statuses(&[signature]).await.map(|r| r.value.into_iter().next()) {
+        if let Some(err) = &st.err {
+            return send(tx, "status", json!({ "signature": signature.to_string(), "status": "failed", "slot": st.slot, "error": submit::decode_tx_error(err, None) })).await;
+        }
+        let (name, level) = match st.confirmation_status() {
+            TransactionConfirmationStatus::Processed => ("processed", 1),
+            TransactionConfirmationStatus::Confirmed => ("confirmed", 2),
+            TransactionConfirmationStatus::Finalized => ("finalized", 3),
+        };
+        stage = level;
+        emit_stage(&rpc, tx, signature, owner.as_ref(), name, st.slot).await?;
+        if stage == 3 {
+            return Ok(());
+        }
+    }
+
+    loop {
+        let (name, level, update) = tokio::select! {
+            Some(u) = processed.next() => ("processed", 1, u),
+            Some(u) = confirmed.next() => ("confirmed", 2, u),
+            Some(u) = finalized.next() => ("finalized", 3, u),
+            Some(lamports) = balances.next() => {
+                let delta = last_lamports.map(|prev| lamports as i128 - prev as i128);
+                last_lamports = Some(lamports);
+                send(tx, "balance", json!({ "owner": owner.map(|o| o.to_string()), "lamports": lamports, "delta_lamports": delta })).await?;
+                continue;
+            }
+            else => return Err("Websocket closed before finalization".to_string()),
+        };
+
+        if let RpcSignatureResult::ProcessedSignature(result) = update.value {
+            if let Some(err) = result.err {
+                return send(tx, "status", json!({ "signature": signature.to_string(), "status": "failed", "slot": update.context.slot, "error": submit::decode_tx_error(&err, None) })).await;
+            }
+        }
+
+        // Notifications can arrive out of order; never step backwards
+        if level <= stage {
+            continue;
+        }
+        stage = level;
+        emit_stage(&rpc, tx, signature, owner.as_ref(), name, update.context.slot).await?;
+        if stage == 3 {
+            return Ok(());
+        }
+    }
+}
+
+/// Send a status event. From `confirmed` on the transaction is fetchable,
+/// so the balance changes it caused are attached.
+async fn emit_stage(
+    rpc: &RpcClient,
+    tx: &mpsc::Sender<StatusEvent>,
+    signature: Signature,
+    owner: Option<&Pubkey>,
+    status: &str,
+    slot: u64,
+) -> Result<(), String> {
+    let mut payload = json!({ "signature": signature.to_string(), "status": status, "slot": slot });
+    if status != "processed" {
+        if let Some(changes) = balance_changes(rpc, &signature, owner).await {
+            payload["balance_changes"] = changes;
+        }
+    }
+    send(tx, "status", payload).await
+}
+
+async fn send(tx: &mpsc::Sender<StatusEvent>, event: &'static str, payload: serde_json::Value) -> Result<(), String> {
+    // A closed channel means the client went away; stop quietly
+    tx.send((event, payload)).await.map_err(|_| "Client disconnected".to_string())
+}
+
+/// SOL and token balance deltas recorded in the transaction's metadata.
+/// Token balances are limited to `owner`'s accounts when an owner is given.
+async fn balance_changes(
+    rpc: &RpcClient,
+    signature: &Signature,
+    owner: Option<&Pubkey>,
+) -> Option<serde_json::Value> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let confirmed = rpc.get_transaction_with_config(signature, config).await.ok()?;
+    let meta = confirmed.transaction.meta?;
+    let versioned = confirmed.transaction.transaction.decode()?;
+
+    // Account order for balances: static keys, then loaded writable, then loaded readonly
+    let mut keys: Vec<String> = versioned.message.static_account_keys().iter().map(|k| k.to_string()).collect();
+    if let Some(loaded) = Option::<UiLoadedAddresses>::from(meta.loaded_addresses.clone()) {
+        keys.extend(loaded.writable);
+        keys.extend(loaded.readonly);
+    }
+
+    let sol: Vec<_> = meta.pre_balances.iter().zip(meta.post_balances.iter()).enumerate()
+        .filter(|(_, (pre, post))| pre != post)
+        .map(|(i, (pre, post))| json!({
+            "account": keys.get(i),
+            "pre_lamports": pre,
+            "post_lamports": post,
+            "delta_lamports": *post as i128 - *pre as i128,
+        }))
+        .collect();
+
+    let pre_tokens = Option::<Vec<_>>::from(meta.pre_token_balances).unwrap_or_default();
+    let post_tokens = Option::<Vec<_>>::from(meta.post_token_balances).unwrap_or_default();
+    let tokens = token_changes(&keys, &pre_tokens, &post_tokens, owner);
+
+    Some(json!({ "sol": sol, "tokens": tokens, "fee_lamports": meta.fee }))
+}
+
+/// Token balance deltas, optionally for one owner's accounts. An account
+/// closed by the transaction has only a pre balance and ends at 0.
+fn token_changes(
+    keys: &[String],
+    pre: &[UiTransactionTokenBalance],
+    post: &[UiTransactionTokenBalance],
+    owner: Option<&Pubkey>,
+) -> Vec<serde_json::Value> {
+    let owner_str = owner.map(|o| o.to_string());
+    let closed = pre.iter().filter(|p| !post.iter().any(|q| q.account_index == p.account_index));
+    post.iter().map(|b| (b, true)).chain(closed.map(|b| (b, false)))
+        .filter(|(b, _)| owner_str.is_none() || Option::<&String>::from(b.owner.as_ref()) == owner_str.as_ref())
+        .filter_map(|(balance, open)| {
+            let amount_of = |b: &UiTransactionTokenBalance| b.ui_token_amount.amount.parse::<i128>().ok();
+            let (pre_amount, post_amount) = if open {
+                let pre_amount = pre.iter().find(|p| p.account_index == balance.account_index).and_then(amount_of);
+                (pre_amount.unwrap_or(0), amount_of(balance)?)
+            } else {
+                (amount_of(balance)?, 0)
+            };
+            (pre_amount != post_amount).then(|| json!({
+                "account": keys.get(balance.account_index as usize),
+                "mint": balance.mint,
+                "decimals": balance.ui_token_amount.decimals,
+                "delta": post_amount - pre_amount,
+                "post_ui_amount": if open { balance.ui_token_amount.ui_amount_string.clone() } else { "0".to_string() },
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_streams_per_ip_and_frees_on_drop() {
+        let limits = Arc::new(StreamLimits::default());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let slots: Vec<StreamSlot> = (0..MAX_STREAMS_PER_IP).map(|_| limits.acquire(ip).unwrap()).collect();
+        assert!(limits.acquire(ip).is_err());
+        assert!(limits.acquire("203.0.113.8".parse().unwrap()).is_ok());
+        drop(slots);
+        assert!(limits.acquire(ip).is_ok());
+    }
+
+    fn balance(index: u8, owner: &Pubkey, amount: &str) -> UiTransactionTokenBalance {
+        serde_json::from_value(json!({
+            "accountIndex": index,
+            "mint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "uiTokenAmount": { "uiAmount": null, "decimals": 6, "amount": amount, "uiAmountString": amount },
+            "owner": owner.to_string(),
+        })).unwrap()
+    }
+
+    #[test]
+    fn token_changes_include_closed_accounts() {
+        let (user, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let keys: Vec<String> = (0..4).map(|i| format!("key{}", i)).collect();
+        let pre = [balance(1, &user, "500"), balance(2, &user, "70"), balance(3, &other, "9")];
+        // Account 1 was spent into, account 2 closed, account 3 unchanged
+        let post = [balance(1, &user, "200"), balance(3, &other, "9")];
+
+        let changes = token_changes(&keys, &pre, &post, Some(&user));
+        let deltas: Vec<_> = changes.iter().map(|c| (c["account"].clone(), c["delta"].clone())).collect();
+        assert_eq!(deltas, vec![(json!("key1"), json!(-300)), (json!("key2"), json!(-70))]);
+        assert_eq!(changes[1]["post_ui_amount"], "0");
+
+        // Without an owner the other wallet's (unchanged) account stays out too
+        assert_eq!(token_changes(&keys, &pre, &post, None).len(), 2);
+        assert!(token_changes(&keys, &pre, &post, Some(&other)).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig, RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::InstructionError,
+    message::VersionedMessage,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
+use serde::Serialize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::nonce;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── SIGNED TRANSACTION SUBMISSION ───────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Resend the transaction this often until it shows up in a status query.
+/// RPC nodes drop transactions under load; rebroadcasting is the standard fix.
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(800);
+/// Give up after this long even if the blockhash is still valid (durable nonce txs)
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Final state of a submission, echoed in `meta`
+#[derive(Serialize, Debug)]
+pub struct SubmitOutcome {
+    pub signature: String,
+    /// confirmed | finalized | failed | expired | pending
+    pub status: String,
+    pub slot: Option<u64>,
+    pub attempts: u32,
+    pub error: Option<serde_json::Value>,
+    pub logs: Vec<String>,
+}
+
+impl SubmitOutcome {
+    fn new(signature: &Signature, status: &str) -> Self {
+        SubmitOutcome {
+            signature: signature.to_string(),
+            status: status.to_string(),
+            slot: None,
+            attempts: 0,
+            error: None,
+            logs: vec![],
+        }
+    }
+
+    pub fn landed(&self) -> bool {
+        self.status == "confirmed" || self.status == "finalized"
+    }
+}
+
+/// Decode a base64 signed transaction (legacy or v0)
+pub fn decode_signed_tx(tx_base64: &str) -> Result<(VersionedTransaction, Signature), String> {
+    let tx_bytes = general_purpose::STANDARD.decode(tx_base64.trim())
+        .map_err(|e| format!("Failed to decode tx: {}", e))?;
+
+    let tx: VersionedTransaction = bincode::deserialize(&tx_bytes)
+        .map_err(|e| format!("Failed to deserialize tx: {}", e))?;
+
+    let signature = tx.signatures.first().copied().unwrap_or_default();
+    if signature == Signature::default() {
+        return Err("Transaction is not signed by the fee payer".to_string());
+    }
+
+    Ok((tx, signature))
+}
+
+/// Preflight, send with rebroadcast, and follow the signature until it reaches
+/// `target` commitment, fails, or its blockhash expires.
+pub async fn submit_and_confirm(
+    rpc: &RpcClient,
+    tx: &VersionedTransaction,
+    signature: Signature,
+    target: CommitmentConfig,
+) -> Result<SubmitOutcome, String> {
+    // 1. Preflight - catch failures before paying fees
+    let sim = rpc.simulate_transaction_with_config(tx, RpcSimulateTransactionConfig {
+        sig_verify: true,
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    }).await.map_err(|e| format!("Preflight failed: {}", e))?;
+
+    if let Some(err) = sim.value.err {
+        let mut outcome = SubmitOutcome::new(&signature, if err == TransactionError::BlockhashNotFound { "expired" } else { "failed" });
+        outcome.error = Some(decode_tx_error(&err, Some(&tx.message)));
+        outcome.logs = sim.value.logs.unwrap_or_default();
+        return Ok(outcome);
+    }
+
+    // 2. Send + poll. Preflight already ran, and we retry ourselves.
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..Default::default()
+    };
+    let durable = nonce::uses_durable_nonce(&tx.message);
+    let blockhash = *tx.message.recent_blockhash();
+    let started = Instant::now();
+    let mut last_send: Option<Instant> = None;
+    let mut landed = false;
+    let mut outcome = SubmitOutcome::new(&signature, "pending");
+
+    loop {
+        if !landed && last_send.is_none_or(|t| t.elapsed() >= REBROADCAST_INTERVAL) {
+            outcome.attempts += 1;
+            if let Err(e) = rpc.send_transaction_with_config(tx, send_config).await {
+                println!("[SUBMIT] {} send attempt {} failed: {}", signature, outcome.attempts, e);
+            }
+            last_send = Some(Instant::now());
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        // A flaky status query isn't the transaction failing; try again next round
+        let status = match rpc.get_signature_statuses(&[signature]).await {
+            Ok(res) => res.value.into_iter().next().flatten(),
+            Err(e) => {
+                println!("[SUBMIT] {} status query failed: {}", signature, e);
+                if started.elapsed() >= SUBMIT_TIMEOUT {
+                    return Ok(outcome);
+                }
+                continue;
+            },
+        };
+
+        if let Some(st) = status {
+            outcome.slot = Some(st.slot);
+            if let Some(err) = &st.err {
+                outcome.status = "failed".to_string();
+                outcome.error = Some(decode_tx_error(err, Some(&tx.message)));
+                outcome.logs = fetch_logs(rpc, &signature).await;
+                return Ok(outcome);
+            }
+            if st.satisfies_commitment(target) {
+                outcome.status = match st.confirmation_status() {
+                    TransactionConfirmationStatus::Finalized => "finalized",
+                    _ => "confirmed",
+                }.to_string();
+                return Ok(outcome);
+            }
+            // Processed but not yet at target: stop resending, keep polling
+            landed = true;
+        } else if !durable && !rpc.is_blockhash_valid(&blockhash, CommitmentConfig::processed()).await.unwrap_or(true) {
+            outcome.status = "expired".to_string();
+            outcome.error = Some(json!({ "kind": "BlockhashExpired", "message": "Blockhash expired before the transaction landed. Rebuild and sign again." }));
+            return Ok(outcome);
+        }
+
+        if started.elapsed() >= SUBMIT_TIMEOUT {
+            return Ok(outcome);
+        }
+    }
+}
+
+/// Best-effort program logs for a failed transaction
+async fn fetch_logs(rpc: &RpcClient, signature: &Signature) -> Vec<String> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    match rpc.get_transaction_with_config(signature, config).await {
+        Ok(tx) => tx.transaction.meta
+            .and_then(|m| Option::<Vec<String>>::from(m.log_messages))
+            .unwrap_or_default(),
+        Err(_) => vec![],
+    }
+}
+
+// ─── ERROR DECODING ─────────────────────────────────────────
+
+/// Turn a `TransactionError` into something a user can act on. When the
+/// message is available the failing instruction is mapped to its program,
+/// and well-known custom error codes get a readable explanation.
+pub fn decode_tx_error(err: &TransactionError, message: Option<&VersionedMessage>) -> serde_json::Value {
+    match err {
+        TransactionError::InstructionError(idx, ix_err) => {
+            let program = message.and_then(|m| {
+                let ix = m.instructions().get(*idx as usize)?;
+                m.static_account_keys().get(ix.program_id_index as usize).map(|k| k.to_string())
+            });
+            let (code, hint) = match ix_err {
+                InstructionError::Custom(code) => (Some(*code), program.as_deref().and_then(|p| custom_error_hint(p, *code))),
+                _ => (None, None),
+            };
+            json!({
+                "kind": "InstructionError",
+                "instruction": idx,
+                "program": program,
+                "code": code,
+                "message": hint.map(str::to_string).unwrap_or_else(|| ix_err.to_string()),
+            })
+        }
+        other => {
+            let debug = format!("{:?}", other);
+            json!({
+                "kind": debug.split(['(', ' ', '{']).next().unwrap_or_default(),
+                "message": other.to_string(),
+            })
+        }
+    }
+}
+
+/// Readable messages for the custom error codes users actually hit
+fn custom_error_hint(program: &str, code: u32) -> Option<&'static str> {
+    match (program, code) {
+        ("11111111111111111111111111111111", 0) => Some("Account already exists"),
+        ("11111111111111111111111111111111", 1) => Some("Insufficient SOL balance"),
+        ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" | "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", 0) => Some("Account would fall below rent-exempt minimum"),
+        ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" | "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", 1) => Some("Insufficient token balance"),
+        ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" | "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", 3) => Some("Token account belongs to a different mint"),
+        ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" | "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", 4) => Some("Token account owner mismatch"),
+        ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" | "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", 17) => Some("Token account is frozen"),
+        ("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4", 6001) => Some("Slippage tolerance exceeded - price moved, try again"),
+        _ => None,
+    }
+}
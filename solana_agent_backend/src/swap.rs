@@ -1,7 +1,11 @@
+use async_trait::async_trait;
+use serde::Serialize;
 use serde_json::json;
 use reqwest::Client;
 use solana_sdk::{
-    pubkey::Pubkey, system_instruction, transaction::Transaction, message::Message,
+    pubkey::Pubkey, system_instruction, transaction::{Transaction, VersionedTransaction}, message::{Message, MessageHeader, VersionedMessage},
+    instruction::{Instruction, CompiledInstruction},
+    packet::PACKET_DATA_SIZE,
 };
 use std::str::FromStr;
 use std::net::SocketAddr;
@@ -26,6 +30,12 @@ pub fn token_mint(symbol: &str) -> Option<&'static str> {
     }
 }
 
+/// Registry symbol for a mint address
+pub fn token_symbol(mint: &str) -> Option<&'static str> {
+    ["SOL", "USDC", "USDT", "BONK", "JUP", "RAY", "WIF"].into_iter()
+        .find(|s| token_mint(s) == Some(mint))
+}
+
 /// Get decimal places for a token
 pub fn token_decimals(symbol: &str) -> u8 {
     match symbol.to_uppercase().as_str() {
@@ -51,6 +61,7 @@ pub fn is_valid_token(symbol: &str) -> bool {
 
 /// Resolve a hostname via Google DNS-over-HTTPS.
 /// This bypasses broken local DNS (e.g. mobile hotspots that can't resolve certain domains).
+#[allow(dead_code)] // Kept for networks with broken DNS; nothing calls it today
 async fn resolve_via_doh(hostname: &str) -> Result<SocketAddr, String> {
     let doh_url = format!("https://dns.google/resolve?name={}&type=A", hostname);
 
@@ -87,6 +98,71 @@ async fn resolve_via_doh(hostname: &str) -> Result<SocketAddr, String> {
 // ─── JUPITER V6 SWAP ────────────────────────────────────────
 // ═══════════════════════════════════════════════════════════════
 
+/// Default slippage for swaps, also used for LP deposits and withdrawals
+pub const DEFAULT_SLIPPAGE_BPS: u16 = 50;
+
+/// Highest slippage we let a user ask for (10%)
+pub const MAX_SLIPPAGE_BPS: u16 = 1000;
+
+/// Jupiter API key (JUPITER_API_KEY), shared by the swap and trigger APIs
+pub fn jupiter_api_key() -> Result<String, String> {
+    let api_key = std::env::var("JUPITER_API_KEY")
+        .unwrap_or_default();
+
+    if api_key.is_empty() {
+        return Err("JUPITER_API_KEY not set in .env. Get a free key at https://portal.jup.ag".to_string());
+    }
+    Ok(api_key)
+}
+
+/// Convert a human amount of a registry token to atomic units using its decimals
+pub fn to_atomic(symbol: &str, amount: f64) -> u64 {
+    (amount * 10f64.powi(token_decimals(symbol) as i32)) as u64
+}
+
+/// Convert atomic units of a registry token back to a human amount
+pub fn from_atomic(symbol: &str, atomic: u64) -> f64 {
+    atomic as f64 / 10f64.powi(token_decimals(symbol) as i32)
+}
+
+/// A built swap with what it's expected to return, echoed in `meta`
+#[derive(Serialize, Debug)]
+pub struct SwapPlan {
+    #[serde(skip)]
+    pub tx_base64: String,
+    pub provider: &'static str,
+    /// Output at the quoted price, in UI units
+    pub expected_out: f64,
+    /// Least the transaction accepts before failing, in UI units
+    pub min_out: f64,
+    pub slippage_bps: u16,
+    /// Pool the swap routes through, when there's exactly one
+    pub pool: Option<String>,
+}
+
+/// Where swaps are quoted and built. Jupiter on mainnet; test networks use
+/// the operator's test AMM (see `testamm`).
+#[async_trait]
+pub trait SwapProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Swap `amount` of `input` for `output`, failing below `slippage_bps`
+    /// of the quoted output
+    async fn build_swap(&self, input: &str, output: &str, amount: f64, user: &str, slippage_bps: u16) -> Result<SwapPlan, String>;
+}
+
+/// Jupiter aggregator routing
+pub struct Jupiter;
+
+#[async_trait]
+impl SwapProvider for Jupiter {
+    fn name(&self) -> &'static str { "jupiter" }
+
+    async fn build_swap(&self, input: &str, output: &str, amount: f64, user: &str, slippage_bps: u16) -> Result<SwapPlan, String> {
+        get_jupiter_swap(input, output, amount, user, slippage_bps).await
+    }
+}
+
 /// Fetch a swap transaction from Jupiter API (api.jup.ag).
 /// Requires a free API key from portal.jup.ag (set JUPITER_API_KEY in .env).
 pub async fn get_jupiter_swap(
@@ -94,13 +170,9 @@ pub async fn get_jupiter_swap(
     output: &str,
     amount: f64,
     user: &str,
-) -> Result<String, String> {
-    let api_key = std::env::var("JUPITER_API_KEY")
-        .unwrap_or_default();
-
-    if api_key.is_empty() {
-        return Err("JUPITER_API_KEY not set in .env. Get a free key at https://portal.jup.ag".to_string());
-    }
+    slippage_bps: u16,
+) -> Result<SwapPlan, String> {
+    let api_key = jupiter_api_key()?;
 
     let client = Client::builder()
         .build()
@@ -112,14 +184,12 @@ pub async fn get_jupiter_swap(
     let output_mint = token_mint(output)
         .ok_or_else(|| format!("Unknown output token: '{}'. Supported: SOL, USDC, USDT, BONK, JUP, RAY, WIF", output))?;
 
-    // Convert human amount to atomic units using proper decimals
-    let decimals = token_decimals(input);
-    let amount_atomic = (amount * 10f64.powi(decimals as i32)) as u64;
+    let amount_atomic = to_atomic(input, amount);
 
     // 1. Get Quote from api.jup.ag
     let quote_url = format!(
-        "https://api.jup.ag/swap/v1/quote?inputMint={}&outputMint={}&amount={}&slippageBps=50",
-        input_mint, output_mint, amount_atomic
+        "https://api.jup.ag/swap/v1/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}",
+        input_mint, output_mint, amount_atomic, slippage_bps
     );
 
     let quote_res = client.get(&quote_url)
@@ -141,6 +211,12 @@ pub async fn get_jupiter_swap(
         return Err(format!("Jupiter quote error: {}", err));
     }
 
+    let quoted = |field: &str| quote_json[field].as_str()
+        .and_then(|a| a.parse::<u64>().ok())
+        .map(|a| from_atomic(output, a))
+        .unwrap_or_default();
+    let (expected_out, min_out) = (quoted("outAmount"), quoted("otherAmountThreshold"));
+
     // 2. Get Swap Transaction (versioned tx - supports lookup tables, fits size limit)
     let swap_req = json!({
         "quoteResponse": quote_json,
@@ -168,7 +244,14 @@ pub async fn get_jupiter_swap(
     let swap_tx = swap_data["swapTransaction"].as_str()
         .ok_or("Jupiter response missing swapTransaction field")?;
 
-    Ok(swap_tx.to_string())
+    Ok(SwapPlan {
+        tx_base64: swap_tx.to_string(),
+        provider: "jupiter",
+        expected_out,
+        min_out,
+        slippage_bps,
+        pool: None,
+    })
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -259,43 +342,166 @@ pub fn append_fee_to_tx(
     ))
 }
 
+// ═══════════════════════════════════════════════════════════════
+// ─── MEMOS ───────────────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+
+/// Longest memo we accept, in bytes. The Memo program has no hard cap, but
+/// every byte counts against the 1232-byte transaction limit and Jupiter
+/// swaps are already close to it.
+pub const MAX_MEMO_BYTES: usize = 256;
+
+/// Validate a user-supplied memo before it goes on-chain
+pub fn validate_memo(memo: &str) -> Result<(), String> {
+    if memo.trim().is_empty() {
+        return Err("Memo cannot be empty".to_string());
+    }
+    if memo.len() > MAX_MEMO_BYTES {
+        return Err(format!("Memo too long ({} bytes, max {})", memo.len(), MAX_MEMO_BYTES));
+    }
+    if memo.chars().any(|c| c.is_control()) {
+        return Err("Memo cannot contain control characters".to_string());
+    }
+    Ok(())
+}
+
+/// Build an SPL Memo instruction signed by `signer`
+pub fn memo_ix(memo: &str, signer: &Pubkey) -> Instruction {
+    spl_memo::build_memo(memo.as_bytes(), &[signer])
+}
+
+/// Append an SPL Memo to an already-built transaction (legacy or versioned).
+/// Used for Jupiter swaps, where we don't control how the transaction is built.
+pub fn append_memo_to_tx(tx_base64: &str, user_pubkey: &str, memo: &str) -> Result<String, String> {
+    validate_memo(memo)?;
+    let user_pub = Pubkey::from_str(user_pubkey)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    append_instruction_to_tx(tx_base64, &memo_ix(memo, &user_pub))
+}
+
+/// Compile `ix` into an existing transaction message.
+///
+/// Accounts already in the message are reused as long as they carry the
+/// privileges `ix` needs. New accounts can only be added as read-only
+/// non-signers (e.g. a program id); for v0 messages every index pointing
+/// into the address lookup tables is shifted to make room for them.
+pub fn append_instruction_to_tx(tx_base64: &str, ix: &Instruction) -> Result<String, String> {
+    let tx_bytes = general_purpose::STANDARD.decode(tx_base64)
+        .map_err(|e| format!("Failed to decode tx: {}", e))?;
+
+    let mut tx: VersionedTransaction = bincode::deserialize(&tx_bytes)
+        .map_err(|e| format!("Failed to deserialize tx: {}", e))?;
+
+    match &mut tx.message {
+        VersionedMessage::Legacy(msg) => {
+            let compiled = compile_into_message(&mut msg.header, &mut msg.account_keys, &mut msg.instructions, ix)?;
+            msg.instructions.push(compiled);
+        }
+        VersionedMessage::V0(msg) => {
+            let compiled = compile_into_message(&mut msg.header, &mut msg.account_keys, &mut msg.instructions, ix)?;
+            msg.instructions.push(compiled);
+        }
+    }
+
+    let bytes = bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?;
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(format!(
+            "The transaction would be {} bytes with the extra instruction, over the {}-byte limit",
+            bytes.len(), PACKET_DATA_SIZE
+        ));
+    }
+    Ok(general_purpose::STANDARD.encode(bytes))
+}
+
+fn compile_into_message(
+    header: &mut MessageHeader,
+    account_keys: &mut Vec<Pubkey>,
+    instructions: &mut [CompiledInstruction],
+    ix: &Instruction,
+) -> Result<CompiledInstruction, String> {
+    let mut index_of = |key: &Pubkey, signer: bool, writable: bool| -> Result<u8, String> {
+        if let Some(idx) = account_keys.iter().position(|k| k == key) {
+            let num_signers = header.num_required_signatures as usize;
+            let is_signer = idx < num_signers;
+            let is_writable = if is_signer {
+                idx < num_signers - header.num_readonly_signed_accounts as usize
+            } else {
+                idx < account_keys.len() - header.num_readonly_unsigned_accounts as usize
+            };
+            if (signer && !is_signer) || (writable && !is_writable) {
+                return Err(format!("Account {} lacks the privileges required by the appended instruction", key));
+            }
+            return Ok(idx as u8);
+        }
+
+        if signer || writable {
+            return Err(format!("Cannot add new signer/writable account {} to a built transaction", key));
+        }
+
+        // New read-only account goes at the end of the static keys. Anything
+        // at or past that index refers to a lookup table entry and moves up by one.
+        let new_idx = account_keys.len() as u8;
+        for compiled in instructions.iter_mut() {
+            if compiled.program_id_index >= new_idx {
+                compiled.program_id_index += 1;
+            }
+            for acc in compiled.accounts.iter_mut() {
+                if *acc >= new_idx {
+                    *acc += 1;
+                }
+            }
+        }
+        account_keys.push(*key);
+        header.num_readonly_unsigned_accounts += 1;
+        Ok(new_idx)
+    };
+
+    let mut accounts = Vec::with_capacity(ix.accounts.len());
+    for meta in &ix.accounts {
+        accounts.push(index_of(&meta.pubkey, meta.is_signer, meta.is_writable)?);
+    }
+    let program_id_index = index_of(&ix.program_id, false, false)?;
+
+    Ok(CompiledInstruction { program_id_index, accounts, data: ix.data.clone() })
+}
+
 // ═══════════════════════════════════════════════════════════════
 // ─── BASIC TRANSACTIONS ──────────────────────────────────────
 // ═══════════════════════════════════════════════════════════════
 
-/// Build native SOL transfer
-pub fn build_transfer_sol(from: &str, to: &str, amount: f64) -> Result<String, String> {
+/// Build native SOL transfer, optionally carrying a memo
+pub fn build_transfer_sol(from: &str, to: &str, amount: f64, memo: Option<&str>) -> Result<String, String> {
     let from_pub = Pubkey::from_str(from).map_err(|e| format!("Invalid from pubkey: {}", e))?;
     let to_pub = Pubkey::from_str(to).unwrap_or(from_pub);
     let lamports = (amount * 1_000_000_000.0) as u64;
 
-    let ix = system_instruction::transfer(&from_pub, &to_pub, lamports);
-    let msg = Message::new(&[ix], Some(&from_pub));
+    let mut instructions = vec![transfer_sol_ix(&from_pub, &to_pub, lamports)];
+    if let Some(memo) = memo {
+        validate_memo(memo)?;
+        instructions.push(memo_ix(memo, &from_pub));
+    }
+
+    let msg = Message::new(&instructions, Some(&from_pub));
     let tx = Transaction::new_unsigned(msg);
 
     Ok(general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap()))
 }
 
-/// Build mock swap (devnet self-transfer)
-pub fn build_mock_swap_tx(user: &str) -> Result<String, String> {
-    build_transfer_sol(user, user, 0.000001)
+/// The system transfer behind `build_transfer_sol`
+pub fn transfer_sol_ix(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    system_instruction::transfer(from, to, lamports)
 }
 
 // ─── SPL TOKEN TRANSFER ─────────────────────────────────────
 
-/// Build an SPL token transfer transaction
+/// Build an SPL token transfer transaction, optionally carrying a memo
 pub fn build_transfer_spl(
     owner: &str,
     recipient: &str,
     mint_address: &str,
     amount_atomic: u64,
+    memo: Option<&str>,
 ) -> Result<String, String> {
-    use spl_token::instruction as token_ix;
-    use spl_associated_token_account::{
-        get_associated_token_address,
-        instruction::create_associated_token_account_idempotent,
-    };
-
     let owner_pub = Pubkey::from_str(owner)
         .map_err(|e| format!("Invalid owner pubkey: {}", e))?;
     let recipient_pub = Pubkey::from_str(recipient)
@@ -303,30 +509,12 @@ pub fn build_transfer_spl(
     let mint_pub = Pubkey::from_str(mint_address)
         .map_err(|e| format!("Invalid mint address: {}", e))?;
 
-    let owner_ata = get_associated_token_address(&owner_pub, &mint_pub);
-    let recipient_ata = get_associated_token_address(&recipient_pub, &mint_pub);
-
-    let mut instructions = vec![];
-
-    instructions.push(
-        create_associated_token_account_idempotent(
-            &owner_pub,
-            &recipient_pub,
-            &mint_pub,
-            &spl_token::id(),
-        )
-    );
+    let mut instructions = transfer_spl_ixs(&owner_pub, &recipient_pub, &mint_pub, amount_atomic)?;
 
-    instructions.push(
-        token_ix::transfer(
-            &spl_token::id(),
-            &owner_ata,
-            &recipient_ata,
-            &owner_pub,
-            &[],
-            amount_atomic,
-        ).map_err(|e| format!("Failed to build transfer ix: {}", e))?
-    );
+    if let Some(memo) = memo {
+        validate_memo(memo)?;
+        instructions.push(memo_ix(memo, &owner_pub));
+    }
 
     let msg = Message::new(&instructions, Some(&owner_pub));
     let tx = Transaction::new_unsigned(msg);
@@ -335,3 +523,26 @@ pub fn build_transfer_spl(
         bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
     ))
 }
+
+/// Create the recipient's ATA if needed, then transfer from the owner's ATA
+pub fn transfer_spl_ixs(
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    amount_atomic: u64,
+) -> Result<Vec<Instruction>, String> {
+    use spl_token::instruction as token_ix;
+    use spl_associated_token_account::{
+        get_associated_token_address,
+        instruction::create_associated_token_account_idempotent,
+    };
+
+    let owner_ata = get_associated_token_address(owner, mint);
+    let recipient_ata = get_associated_token_address(recipient, mint);
+
+    Ok(vec![
+        create_associated_token_account_idempotent(owner, recipient, mint, &spl_token::id()),
+        token_ix::transfer(&spl_token::id(), &owner_ata, &recipient_ata, owner, &[], amount_atomic)
+            .map_err(|e| format!("Failed to build transfer ix: {}", e))?,
+    ])
+}
@@ -0,0 +1,486 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token::instruction::AuthorityType;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::faucet::TestMints;
+use crate::prices::reference_price;
+use crate::rpc;
+use crate::swap::{self, SwapPlan, SwapProvider};
+use crate::wsol;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── TEST AMM (DEVNET SWAPS) ─────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Jupiter doesn't route on devnet, so test-network swaps go through SPL
+// token-swap pools the operator creates and seeds between faucet test mints
+// (SOL is wrapped). Pools are made on the first swap of a pair and priced
+// from the registry's reference prices; after that they move like any constant-product
+// pool. Instructions are hand-encoded for spl-token-swap 3.0; set
+// TEST_AMM_PROGRAM to use a different deployment of it.
+
+/// The SPL token-swap deployment on devnet / testnet
+const TOKEN_SWAP_PROGRAM: Pubkey = solana_sdk::pubkey!("SwapsVeCiPHMUAtzQWZw7RjsKjgCjhwU55QGu4U1Szw");
+
+/// `SwapVersion` byte + packed `SwapV1`
+const SWAP_STATE_LEN: usize = 324;
+
+/// Pool token decimals; only the pool's own LP accounting sees them
+const POOL_TOKEN_DECIMALS: u8 = 9;
+
+const INITIALIZE: u8 = 0;
+const SWAP: u8 = 1;
+const CURVE_CONSTANT_PRODUCT: u8 = 0;
+
+/// The fee-account owner the production token-swap build requires. A
+/// deployment built without that constraint can take another owner through
+/// TEST_AMM_FEE_OWNER.
+const PRODUCTION_FEE_OWNER: Pubkey = solana_sdk::pubkey!("HfoTxFR1Tm6kGmWgYWD6J7YHVy1UwqSULUGVLXkJqaKN");
+
+/// (numerator, denominator) pairs in `Fees` order. These are the values the
+/// production token-swap build enforces.
+const TRADE_FEE: (u64, u64) = (25, 10_000);
+const OWNER_TRADE_FEE: (u64, u64) = (5, 10_000);
+const OWNER_WITHDRAW_FEE: (u64, u64) = (0, 0);
+const HOST_FEE: (u64, u64) = (20, 100);
+
+/// USD value of each side of a new token/token pool
+const POOL_SEED_USD: f64 = 100_000.0;
+
+/// SOL the operator puts into a new SOL pool; test SOL is scarcer than test tokens
+const POOL_SEED_SOL: f64 = 2.0;
+
+fn program_id() -> Pubkey {
+    std::env::var("TEST_AMM_PROGRAM").ok()
+        .and_then(|p| Pubkey::from_str(p.trim()).ok())
+        .unwrap_or(TOKEN_SWAP_PROGRAM)
+}
+
+/// A seeded pool; token_a sorts before token_b
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TestPool {
+    pub network: String,
+    pub program: String,
+    pub token_a: String,
+    pub token_b: String,
+    pub swap: String,
+    pub authority: String,
+    pub mint_a: String,
+    pub mint_b: String,
+    pub vault_a: String,
+    pub vault_b: String,
+    pub pool_mint: String,
+    pub fee_account: String,
+}
+
+/// Operator pools, stored as JSON in DATA_DIR/test_pools.json
+pub struct TestPools {
+    path: PathBuf,
+    pools: Mutex<Vec<TestPool>>,
+    /// Serializes pool creation so two first swaps don't seed two pools
+    creating: tokio::sync::Mutex<()>,
+}
+
+impl TestPools {
+    pub fn load(path: PathBuf) -> Self {
+        let pools = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        TestPools {
+            path,
+            pools: Mutex::new(pools),
+            creating: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn get(&self, network: &str, token_a: &str, token_b: &str) -> Option<TestPool> {
+        let program = program_id().to_string();
+        self.pools.lock().unwrap().iter()
+            .find(|p| p.network == network && p.program == program && p.token_a == token_a && p.token_b == token_b)
+            .cloned()
+    }
+
+    fn add(&self, pool: TestPool) -> Result<(), String> {
+        let mut pools = self.pools.lock().unwrap();
+        pools.push(pool);
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+        }
+        let json = serde_json::to_vec_pretty(&*pools).map_err(|e| format!("Serialize error: {}", e))?;
+        std::fs::write(&self.path, json).map_err(|e| format!("Failed to save test pools: {}", e))
+    }
+
+    /// The pool for a sorted pair, creating and seeding it on first use
+    async fn ensure(
+        &self,
+        rpc: &RpcClient,
+        operator: &Keypair,
+        mints: &TestMints,
+        network: &str,
+        pair: (&str, &str),
+    ) -> Result<TestPool, String> {
+        if let Some(pool) = self.get(network, pair.0, pair.1) {
+            return Ok(pool);
+        }
+        let _guard = self.creating.lock().await;
+        if let Some(pool) = self.get(network, pair.0, pair.1) {
+            return Ok(pool);
+        }
+
+        let mint_a = pool_mint_for(rpc, operator, mints, network, pair.0).await?;
+        let mint_b = pool_mint_for(rpc, operator, mints, network, pair.1).await?;
+        let seeds = ((mint_a, seed_amount(pair.0, pair)?), (mint_b, seed_amount(pair.1, pair)?));
+
+        let pool = create_pool(rpc, operator, network, pair, seeds.0, seeds.1).await?;
+        println!("[TEST-AMM] Seeded {}/{} pool on {}: {}", pair.0, pair.1, network, pool.swap);
+        self.add(pool.clone())?;
+        Ok(pool)
+    }
+}
+
+/// The mint a pool holds for `symbol`: native wSOL, or the faucet's test mint
+async fn pool_mint_for(rpc: &RpcClient, operator: &Keypair, mints: &TestMints, network: &str, symbol: &str) -> Result<Pubkey, String> {
+    if symbol == "SOL" {
+        return Ok(wsol::wsol_mint());
+    }
+    mints.ensure(rpc, operator, network, symbol).await
+}
+
+/// Atomic amount of `symbol` one side of a new `pair` pool starts with
+fn seed_amount(symbol: &str, pair: (&str, &str)) -> Result<u64, String> {
+    let price = reference_price(symbol)
+        .ok_or_else(|| format!("No reference price to seed a {} pool", symbol))?;
+    let side_usd = if pair.0 == "SOL" || pair.1 == "SOL" {
+        POOL_SEED_SOL * reference_price("SOL").unwrap_or_default()
+    } else {
+        POOL_SEED_USD
+    };
+    Ok(swap::to_atomic(symbol, side_usd / price))
+}
+
+/// Create the pool's accounts, fund both vaults and initialize the swap. The
+/// operator pays for and signs everything. The first transaction leaves every
+/// account operator-owned; the second hands them to the swap authority as it
+/// initializes, so if it fails the vaults can still be closed and their rent
+/// reclaimed. Only the pool mint's rent is lost then, as mints can't be closed.
+async fn create_pool(
+    rpc: &RpcClient,
+    operator: &Keypair,
+    network: &str,
+    pair: (&str, &str),
+    (mint_a, seed_a): (Pubkey, u64),
+    (mint_b, seed_b): (Pubkey, u64),
+) -> Result<TestPool, String> {
+    let program = program_id();
+    let payer = operator.pubkey();
+    let swap_kp = Keypair::new();
+    let pool_mint_kp = Keypair::new();
+    let vault_a_kp = Keypair::new();
+    let vault_b_kp = Keypair::new();
+    let destination_kp = Keypair::new();
+    let (authority, _) = Pubkey::find_program_address(&[swap_kp.pubkey().as_ref()], &program);
+
+    let mint_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN).await
+        .map_err(|e| format!("Failed to fetch mint rent: {}", e))?;
+    let account_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN).await
+        .map_err(|e| format!("Failed to fetch token account rent: {}", e))?;
+    let swap_rent = rpc.get_minimum_balance_for_rent_exemption(SWAP_STATE_LEN).await
+        .map_err(|e| format!("Failed to fetch swap state rent: {}", e))?;
+
+    let token_account = |account: &Pubkey, mint: &Pubkey, owner: &Pubkey| -> Result<Vec<Instruction>, String> {
+        Ok(vec![
+            system_instruction::create_account(&payer, account, account_rent, spl_token::state::Account::LEN as u64, &spl_token::id()),
+            spl_token::instruction::initialize_account3(&spl_token::id(), account, mint, owner)
+                .map_err(|e| format!("Failed to build initialize_account ix: {}", e))?,
+        ])
+    };
+
+    // 1. Pool mint and token accounts, all operator-owned for now. The
+    //    initial pool tokens go to the operator.
+    let mut accounts = vec![
+        system_instruction::create_account(&payer, &pool_mint_kp.pubkey(), mint_rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), &pool_mint_kp.pubkey(), &payer, None, POOL_TOKEN_DECIMALS)
+            .map_err(|e| format!("Failed to build initialize_mint ix: {}", e))?,
+    ];
+    accounts.extend(token_account(&vault_a_kp.pubkey(), &mint_a, &payer)?);
+    accounts.extend(token_account(&vault_b_kp.pubkey(), &mint_b, &payer)?);
+    accounts.extend(token_account(&destination_kp.pubkey(), &pool_mint_kp.pubkey(), &payer)?);
+    send(rpc, &accounts, operator, &[&pool_mint_kp, &vault_a_kp, &vault_b_kp, &destination_kp]).await?;
+
+    // 2. Seed both vaults, hand the vaults and pool mint to the swap
+    //    authority, then create and initialize the swap
+    let fee_owner = std::env::var("TEST_AMM_FEE_OWNER").ok()
+        .and_then(|p| Pubkey::from_str(p.trim()).ok())
+        .unwrap_or(PRODUCTION_FEE_OWNER);
+    let fee_account = get_associated_token_address(&fee_owner, &pool_mint_kp.pubkey());
+
+    let mut init = fund_vault(operator, &vault_a_kp.pubkey(), &mint_a, pair.0, seed_a)?;
+    init.extend(fund_vault(operator, &vault_b_kp.pubkey(), &mint_b, pair.1, seed_b)?);
+    for (account, kind) in [
+        (vault_a_kp.pubkey(), AuthorityType::AccountOwner),
+        (vault_b_kp.pubkey(), AuthorityType::AccountOwner),
+        (pool_mint_kp.pubkey(), AuthorityType::MintTokens),
+    ] {
+        init.push(spl_token::instruction::set_authority(&spl_token::id(), &account, Some(&authority), kind, &payer, &[])
+            .map_err(|e| format!("Failed to build set_authority ix: {}", e))?);
+    }
+    init.push(create_associated_token_account_idempotent(&payer, &fee_owner, &pool_mint_kp.pubkey(), &spl_token::id()));
+    init.push(system_instruction::create_account(&payer, &swap_kp.pubkey(), swap_rent, SWAP_STATE_LEN as u64, &program));
+    init.push(initialize_ix(
+        &program,
+        &swap_kp.pubkey(),
+        &authority,
+        (&vault_a_kp.pubkey(), &vault_b_kp.pubkey()),
+        &pool_mint_kp.pubkey(),
+        &fee_account,
+        &destination_kp.pubkey(),
+    ));
+    if let Err(e) = send(rpc, &init, operator, &[&swap_kp]).await {
+        reclaim(rpc, operator, &[vault_a_kp.pubkey(), vault_b_kp.pubkey(), destination_kp.pubkey()]).await;
+        return Err(e);
+    }
+
+    Ok(TestPool {
+        network: network.to_string(),
+        program: program.to_string(),
+        token_a: pair.0.to_string(),
+        token_b: pair.1.to_string(),
+        swap: swap_kp.pubkey().to_string(),
+        authority: authority.to_string(),
+        mint_a: mint_a.to_string(),
+        mint_b: mint_b.to_string(),
+        vault_a: vault_a_kp.pubkey().to_string(),
+        vault_b: vault_b_kp.pubkey().to_string(),
+        pool_mint: pool_mint_kp.pubkey().to_string(),
+        fee_account: fee_account.to_string(),
+    })
+}
+
+/// Put `amount` into a vault: minted for test tokens, wrapped for SOL
+fn fund_vault(operator: &Keypair, vault: &Pubkey, mint: &Pubkey, symbol: &str, amount: u64) -> Result<Vec<Instruction>, String> {
+    if symbol == "SOL" {
+        return Ok(vec![
+            system_instruction::transfer(&operator.pubkey(), vault, amount),
+            spl_token::instruction::sync_native(&spl_token::id(), vault)
+                .map_err(|e| format!("Failed to build sync_native ix: {}", e))?,
+        ]);
+    }
+    Ok(vec![
+        spl_token::instruction::mint_to_checked(
+            &spl_token::id(), mint, vault, &operator.pubkey(), &[], amount, swap::token_decimals(symbol),
+        ).map_err(|e| format!("Failed to build mint_to ix: {}", e))?,
+    ])
+}
+
+/// Close token accounts left behind by a failed pool setup, returning their
+/// rent to the operator. Best-effort: a failure is only logged.
+async fn reclaim(rpc: &RpcClient, operator: &Keypair, accounts: &[Pubkey]) {
+    let payer = operator.pubkey();
+    let closes: Result<Vec<Instruction>, _> = accounts.iter()
+        .map(|account| spl_token::instruction::close_account(&spl_token::id(), account, &payer, &payer, &[]))
+        .collect();
+    let result = match closes {
+        Ok(ixs) => send(rpc, &ixs, operator, &[]).await,
+        Err(e) => Err(format!("Failed to build close_account ix: {}", e)),
+    };
+    match result {
+        Ok(()) => println!("[TEST-AMM] Reclaimed {} accounts from a failed pool setup", accounts.len()),
+        Err(e) => println!("[TEST-AMM] Couldn't reclaim {:?} after a failed pool setup: {}", accounts, e),
+    }
+}
+
+async fn send(rpc: &RpcClient, instructions: &[Instruction], operator: &Keypair, extra: &[&Keypair]) -> Result<(), String> {
+    let blockhash = rpc.get_latest_blockhash().await
+        .map_err(|e| format!("Failed to fetch blockhash: {}", e))?;
+    let mut signers: Vec<&Keypair> = vec![operator];
+    signers.extend_from_slice(extra);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&operator.pubkey()), &signers, blockhash);
+    rpc.send_and_confirm_transaction(&tx).await
+        .map_err(|e| format!("Test pool setup failed: {}", e))?;
+    Ok(())
+}
+
+// ─── INSTRUCTIONS ───────────────────────────────────────────
+
+/// `Initialize { fees, swap_curve }` with a constant-product curve
+fn initialize_ix(
+    program: &Pubkey,
+    swap: &Pubkey,
+    authority: &Pubkey,
+    (vault_a, vault_b): (&Pubkey, &Pubkey),
+    pool_mint: &Pubkey,
+    fee_account: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let mut data = vec![INITIALIZE];
+    for (numerator, denominator) in [TRADE_FEE, OWNER_TRADE_FEE, OWNER_WITHDRAW_FEE, HOST_FEE] {
+        data.extend_from_slice(&numerator.to_le_bytes());
+        data.extend_from_slice(&denominator.to_le_bytes());
+    }
+    // Curve type, then the calculator packed into 32 bytes (constant product has no parameters)
+    data.push(CURVE_CONSTANT_PRODUCT);
+    data.extend_from_slice(&[0u8; 32]);
+
+    Instruction {
+        program_id: *program,
+        accounts: vec![
+            AccountMeta::new(*swap, true),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new_readonly(*vault_a, false),
+            AccountMeta::new_readonly(*vault_b, false),
+            AccountMeta::new(*pool_mint, false),
+            AccountMeta::new_readonly(*fee_account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data,
+    }
+}
+
+/// One side of a pool as seen by a swap
+struct Side {
+    mint: Pubkey,
+    vault: Pubkey,
+    user_account: Pubkey,
+}
+
+/// `Swap { amount_in, minimum_amount_out }` moving `from` into the pool and `to` out
+fn swap_ix(pool: &TestPool, user: &Pubkey, from: &Side, to: &Side, amount_in: u64, minimum_out: u64) -> Result<Instruction, String> {
+    let mut data = vec![SWAP];
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_out.to_le_bytes());
+
+    Ok(Instruction {
+        program_id: key(&pool.program)?,
+        accounts: vec![
+            AccountMeta::new_readonly(key(&pool.swap)?, false),
+            AccountMeta::new_readonly(key(&pool.authority)?, false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new(from.user_account, false),
+            AccountMeta::new(from.vault, false),
+            AccountMeta::new(to.vault, false),
+            AccountMeta::new(to.user_account, false),
+            AccountMeta::new(key(&pool.pool_mint)?, false),
+            AccountMeta::new(key(&pool.fee_account)?, false),
+            AccountMeta::new_readonly(from.mint, false),
+            AccountMeta::new_readonly(to.mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data,
+    })
+}
+
+fn key(s: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(s).map_err(|e| format!("Invalid test pool account {}: {}", s, e))
+}
+
+/// Output of a constant-product swap after the trade and owner fees
+fn quote(amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+    let fee = |(numerator, denominator): (u64, u64)| {
+        let f = (amount_in as u128 * numerator as u128 / denominator as u128) as u64;
+        // The program charges at least one unit whenever a fee is set
+        if f == 0 && numerator > 0 { 1 } else { f }
+    };
+    let in_after_fees = amount_in.saturating_sub(fee(TRADE_FEE) + fee(OWNER_TRADE_FEE)) as u128;
+    (reserve_out as u128 * in_after_fees / (reserve_in as u128 + in_after_fees)) as u64
+}
+
+// ─── PROVIDER ───────────────────────────────────────────────
+
+/// Swaps against the operator's test pools on a test network
+pub struct TestAmm {
+    pub network: String,
+    pub operator: Arc<Keypair>,
+    pub mints: Arc<TestMints>,
+    pub pools: Arc<TestPools>,
+}
+
+#[async_trait]
+impl SwapProvider for TestAmm {
+    fn name(&self) -> &'static str { "test-amm" }
+
+    async fn build_swap(&self, input: &str, output: &str, amount: f64, user: &str, slippage_bps: u16) -> Result<SwapPlan, String> {
+        let input = input.to_uppercase();
+        let output = output.to_uppercase();
+        if input == output {
+            return Err("Input and output tokens must differ".to_string());
+        }
+        let user = Pubkey::from_str(user)
+            .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+        let amount_in = swap::to_atomic(&input, amount);
+        if amount_in == 0 {
+            return Err("Swap amount must be greater than 0".to_string());
+        }
+
+        let rpc = rpc::client(&self.network);
+        let pair = if input < output { (input.as_str(), output.as_str()) } else { (output.as_str(), input.as_str()) };
+        let pool = self.pools.ensure(&rpc, &self.operator, &self.mints, &self.network, pair).await?;
+
+        let side = |mint: &str, vault: &str| -> Result<Side, String> {
+            let mint = key(mint)?;
+            Ok(Side { mint, vault: key(vault)?, user_account: get_associated_token_address(&user, &mint) })
+        };
+        let a = side(&pool.mint_a, &pool.vault_a)?;
+        let b = side(&pool.mint_b, &pool.vault_b)?;
+        let (from, to) = if pool.token_a == input { (a, b) } else { (b, a) };
+
+        let vaults = rpc.get_multiple_accounts(&[from.vault, to.vault]).await
+            .map_err(|e| format!("Failed to fetch test pool vaults: {}", e))?;
+        let reserve = |i: usize| vaults[i].as_ref()
+            .and_then(|a| a.data.get(64..72))
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| "Test pool vault missing".to_string());
+        let (reserve_in, reserve_out) = (reserve(0)?, reserve(1)?);
+
+        let expected_out = quote(amount_in, reserve_in, reserve_out);
+        if expected_out == 0 {
+            return Err("Swap amount too small for the test pool".to_string());
+        }
+        let minimum_out = (expected_out as u128 * (10_000 - slippage_bps as u128) / 10_000) as u64;
+
+        let mut instructions = Vec::new();
+        if input == "SOL" {
+            instructions.extend(wsol::wrap_ixs(&user, amount_in)?);
+        }
+        instructions.push(create_associated_token_account_idempotent(&user, &user, &to.mint, &spl_token::id()));
+        instructions.push(swap_ix(&pool, &user, &from, &to, amount_in, minimum_out)?);
+        // Unwrap back to SOL only through a wSOL account this swap creates
+        if (input == "SOL" || output == "SOL") && !wsol::has_wsol_account(&rpc, &user).await? {
+            instructions.push(wsol::unwrap_ix(&user)?);
+        }
+
+        let tx = Transaction::new_unsigned(Message::new(&instructions, Some(&user)));
+        Ok(SwapPlan {
+            tx_base64: general_purpose::STANDARD.encode(
+                bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+            ),
+            provider: self.name(),
+            expected_out: swap::from_atomic(&output, expected_out),
+            min_out: swap::from_atomic(&output, minimum_out),
+            slippage_bps,
+            pool: Some(pool.swap),
+        })
+    }
+}
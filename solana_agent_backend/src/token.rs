@@ -0,0 +1,377 @@
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::instructions::CreateMetadataAccountV3Builder;
+use mpl_token_metadata::types::DataV2;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token::instruction::AuthorityType;
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::nft::{MAX_NAME_LEN, MAX_SYMBOL_LEN};
+use crate::nonce::NonceInfo;
+use crate::swap;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── FUNGIBLE TOKENS & AUTHORITIES ───────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Token creation and the authority instructions that follow it. Everything
+// is plain spl_token; Token-2022 mints share the base mint layout and these
+// instruction encodings, so the same builders are re-pointed at its program.
+
+pub const TOKEN_2022_ID: Pubkey = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Most decimals a created token may have (SOL's own precision)
+pub const MAX_DECIMALS: u8 = 9;
+
+/// Base mint layout offsets, shared by both token programs
+const MINT_AUTHORITY_OFFSET: usize = 0;
+const MINT_SUPPLY_OFFSET: usize = 36;
+const MINT_DECIMALS_OFFSET: usize = 44;
+const FREEZE_AUTHORITY_OFFSET: usize = 46;
+
+/// Offset of `state` in a token account (1 = initialized, 2 = frozen)
+const ACCOUNT_STATE_OFFSET: usize = 108;
+const ACCOUNT_STATE_FROZEN: u8 = 2;
+
+/// The parts of a mint the authority intents check
+pub struct MintInfo {
+    pub program: Pubkey,
+    pub decimals: u8,
+    pub supply: u64,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+impl MintInfo {
+    /// UI amount to base units, rejecting amounts that don't fit a u64
+    pub fn to_atomic(&self, ui: f64) -> Result<u64, String> {
+        to_atomic(ui, self.decimals)
+    }
+}
+
+pub(crate) fn to_atomic(ui: f64, decimals: u8) -> Result<u64, String> {
+    let atomic = (ui * 10f64.powi(decimals as i32)).round();
+    if !atomic.is_finite() || atomic < 0.0 || atomic > u64::MAX as f64 {
+        return Err(format!("Amount {} is too large for {} decimals", ui, decimals));
+    }
+    Ok(atomic as u64)
+}
+
+/// A `COption<Pubkey>`: 4-byte tag then the key
+fn read_coption_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    let tag = data.get(offset..offset + 4)?;
+    if tag != [1, 0, 0, 0] {
+        return None;
+    }
+    Pubkey::try_from(data.get(offset + 4..offset + 36)?).ok()
+}
+
+/// Registry symbol or raw mint address
+pub fn resolve_mint(query: &str) -> Result<Pubkey, String> {
+    swap::token_mint(&query.trim().to_uppercase())
+        .map(Pubkey::from_str)
+        .unwrap_or_else(|| Pubkey::from_str(query.trim()))
+        .map_err(|_| format!("Unknown token '{}'. Use a symbol (USDC, BONK, ...) or a mint address", query))
+}
+
+pub async fn fetch_mint(rpc: &RpcClient, mint: &Pubkey) -> Result<MintInfo, String> {
+    let account = rpc.get_account(mint).await
+        .map_err(|e| format!("Failed to fetch mint {}: {}", mint, e))?;
+    let program = account.owner;
+    let data = account.data;
+    if (program != spl_token::id() && program != TOKEN_2022_ID) || data.len() < spl_token::state::Mint::LEN {
+        return Err(format!("{} is not a token mint", mint));
+    }
+
+    let supply = u64::from_le_bytes(data[MINT_SUPPLY_OFFSET..MINT_SUPPLY_OFFSET + 8].try_into().unwrap());
+    Ok(MintInfo {
+        program,
+        decimals: data[MINT_DECIMALS_OFFSET],
+        supply,
+        mint_authority: read_coption_pubkey(&data, MINT_AUTHORITY_OFFSET),
+        freeze_authority: read_coption_pubkey(&data, FREEZE_AUTHORITY_OFFSET),
+    })
+}
+
+/// Token instructions share their layout across both token programs; only
+/// the program id differs
+pub fn with_program(mut ix: Instruction, program: &Pubkey) -> Instruction {
+    ix.program_id = *program;
+    ix
+}
+
+fn encode_unsigned(instructions: &[Instruction], payer: &Pubkey) -> Result<String, String> {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    Ok(general_purpose::STANDARD.encode(
+        bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+    ))
+}
+
+// ─── CREATE ─────────────────────────────────────────────────
+
+/// A freshly built token launch
+pub struct CreatedToken {
+    pub tx_base64: String,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub metadata: Pubkey,
+    pub supply_atomic: u64,
+}
+
+/// Create a mint with Metaplex metadata and mint the initial supply to the
+/// user. The user keeps both mint and freeze authority; the mint keypair is
+/// generated here and partially signs.
+pub async fn build_create_token_tx(
+    rpc: &RpcClient,
+    user: &Pubkey,
+    name: &str,
+    symbol: &str,
+    decimals: u8,
+    supply: f64,
+    nonce: Option<&NonceInfo>,
+) -> Result<CreatedToken, String> {
+    if name.trim().is_empty() {
+        return Err("Token name is required".to_string());
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(format!("Token name too long ({} bytes, max {})", name.len(), MAX_NAME_LEN));
+    }
+    if symbol.len() > MAX_SYMBOL_LEN {
+        return Err(format!("Token symbol too long ({} bytes, max {})", symbol.len(), MAX_SYMBOL_LEN));
+    }
+    if decimals > MAX_DECIMALS {
+        return Err(format!("Decimals must be between 0 and {}", MAX_DECIMALS));
+    }
+    if supply < 0.0 {
+        return Err("Initial supply can't be negative".to_string());
+    }
+    let supply_atomic = to_atomic(supply, decimals)?;
+
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let token_account = get_associated_token_address_with_program_id(user, &mint, &spl_token::id());
+    let (metadata, _) = Metadata::find_pda(&mint);
+
+    let mint_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN).await
+        .map_err(|e| format!("Failed to fetch mint rent: {}", e))?;
+
+    let mut instructions = vec![
+        system_instruction::create_account(
+            user,
+            &mint,
+            mint_rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), &mint, user, Some(user), decimals)
+            .map_err(|e| format!("Failed to build initialize_mint ix: {}", e))?,
+        CreateMetadataAccountV3Builder::new()
+            .metadata(metadata)
+            .mint(mint)
+            .mint_authority(*user)
+            .payer(*user)
+            .update_authority(*user, true)
+            .data(DataV2 {
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+                uri: String::new(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            })
+            .is_mutable(true)
+            .instruction(),
+    ];
+    if supply_atomic > 0 {
+        instructions.push(create_associated_token_account_idempotent(user, user, &mint, &spl_token::id()));
+        instructions.push(
+            spl_token::instruction::mint_to_checked(&spl_token::id(), &mint, &token_account, user, &[], supply_atomic, decimals)
+                .map_err(|e| format!("Failed to build mint_to ix: {}", e))?,
+        );
+    }
+
+    let (msg, blockhash) = match nonce {
+        Some(info) => (
+            Message::new_with_nonce(instructions, Some(user), &info.address, &info.authority),
+            info.blockhash,
+        ),
+        None => (
+            Message::new(&instructions, Some(user)),
+            rpc.get_latest_blockhash().await
+                .map_err(|e| format!("Failed to fetch blockhash: {}", e))?,
+        ),
+    };
+
+    let mut tx = Transaction::new_unsigned(msg);
+    tx.try_partial_sign(&[&mint_kp], blockhash)
+        .map_err(|e| format!("Failed to sign with mint keypair: {}", e))?;
+
+    Ok(CreatedToken {
+        tx_base64: general_purpose::STANDARD.encode(
+            bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+        ),
+        mint,
+        token_account,
+        metadata,
+        supply_atomic,
+    })
+}
+
+// ─── AUTHORITIES ────────────────────────────────────────────
+
+/// Which mint authority an intent targets
+#[derive(Clone, Copy, Debug)]
+pub enum AuthorityKind {
+    Mint,
+    Freeze,
+}
+
+impl AuthorityKind {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "mint" | "mint_tokens" | "minting" => Ok(AuthorityKind::Mint),
+            "freeze" | "freeze_account" | "freezing" => Ok(AuthorityKind::Freeze),
+            other => Err(format!("Unknown authority '{}'. Use mint or freeze.", other)),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AuthorityKind::Mint => "mint",
+            AuthorityKind::Freeze => "freeze",
+        }
+    }
+
+    fn current(self, info: &MintInfo) -> Option<Pubkey> {
+        match self {
+            AuthorityKind::Mint => info.mint_authority,
+            AuthorityKind::Freeze => info.freeze_authority,
+        }
+    }
+
+    fn spl(self) -> AuthorityType {
+        match self {
+            AuthorityKind::Mint => AuthorityType::MintTokens,
+            AuthorityKind::Freeze => AuthorityType::FreezeAccount,
+        }
+    }
+}
+
+/// Fail unless `user` currently holds `kind` on the mint
+fn require_authority(info: &MintInfo, kind: AuthorityKind, mint: &Pubkey, user: &Pubkey) -> Result<(), String> {
+    match kind.current(info) {
+        Some(current) if current == *user => Ok(()),
+        Some(current) => Err(format!("The {} authority of {} is {}, not your wallet", kind.label(), mint, current)),
+        None => Err(format!("{} has no {} authority; it was revoked", mint, kind.label())),
+    }
+}
+
+/// A built mint-more
+pub struct MintToPlan {
+    pub tx_base64: String,
+    pub token_account: Pubkey,
+    pub amount_atomic: u64,
+    pub decimals: u8,
+    pub new_supply: u64,
+}
+
+/// Mint `amount` (UI units) of `mint` to `recipient`'s associated account,
+/// creating it if needed. The user must be the mint authority.
+pub async fn build_mint_to_tx(
+    rpc: &RpcClient,
+    user: &Pubkey,
+    mint: &Pubkey,
+    amount: f64,
+    recipient: &Pubkey,
+) -> Result<MintToPlan, String> {
+    let info = fetch_mint(rpc, mint).await?;
+    require_authority(&info, AuthorityKind::Mint, mint, user)?;
+    if amount <= 0.0 {
+        return Err("Amount to mint must be greater than 0".to_string());
+    }
+    let amount_atomic = info.to_atomic(amount)?;
+    let new_supply = info.supply.checked_add(amount_atomic)
+        .ok_or_else(|| "Minting that much would overflow the token's supply".to_string())?;
+
+    let token_account = get_associated_token_address_with_program_id(recipient, mint, &info.program);
+    let instructions = vec![
+        create_associated_token_account_idempotent(user, recipient, mint, &info.program),
+        with_program(
+            spl_token::instruction::mint_to_checked(&spl_token::id(), mint, &token_account, user, &[], amount_atomic, info.decimals)
+                .map_err(|e| format!("Failed to build mint_to ix: {}", e))?,
+            &info.program,
+        ),
+    ];
+
+    Ok(MintToPlan {
+        tx_base64: encode_unsigned(&instructions, user)?,
+        token_account,
+        amount_atomic,
+        decimals: info.decimals,
+        new_supply,
+    })
+}
+
+/// Hand `kind` over to `new_authority`, or revoke it for good with `None`
+pub async fn build_set_authority_tx(
+    rpc: &RpcClient,
+    user: &Pubkey,
+    mint: &Pubkey,
+    kind: AuthorityKind,
+    new_authority: Option<&Pubkey>,
+) -> Result<String, String> {
+    let info = fetch_mint(rpc, mint).await?;
+    require_authority(&info, kind, mint, user)?;
+    if new_authority == Some(user) {
+        return Err(format!("You already hold the {} authority", kind.label()));
+    }
+
+    let ix = spl_token::instruction::set_authority(&spl_token::id(), mint, new_authority, kind.spl(), user, &[])
+        .map_err(|e| format!("Failed to build set_authority ix: {}", e))?;
+    encode_unsigned(&[with_program(ix, &info.program)], user)
+}
+
+/// Freeze or thaw `holder`'s associated account for `mint`. The user must be
+/// the freeze authority.
+pub async fn build_freeze_tx(
+    rpc: &RpcClient,
+    user: &Pubkey,
+    mint: &Pubkey,
+    holder: &Pubkey,
+    freeze: bool,
+) -> Result<(String, Pubkey), String> {
+    let info = fetch_mint(rpc, mint).await?;
+    require_authority(&info, AuthorityKind::Freeze, mint, user)?;
+
+    let token_account = get_associated_token_address_with_program_id(holder, mint, &info.program);
+    let data = rpc.get_account_data(&token_account).await
+        .map_err(|_| format!("{} has no token account for {}", holder, mint))?;
+    let frozen = data.get(ACCOUNT_STATE_OFFSET) == Some(&ACCOUNT_STATE_FROZEN);
+    if frozen == freeze {
+        return Err(format!("{}'s account is already {}", holder, if frozen { "frozen" } else { "active" }));
+    }
+
+    let ix = if freeze {
+        spl_token::instruction::freeze_account(&spl_token::id(), &token_account, mint, user, &[])
+    } else {
+        spl_token::instruction::thaw_account(&spl_token::id(), &token_account, mint, user, &[])
+    }
+    .map_err(|e| format!("Failed to build freeze ix: {}", e))?;
+
+    Ok((encode_unsigned(&[with_program(ix, &info.program)], user)?, token_account))
+}
@@ -0,0 +1,224 @@
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::swap::{jupiter_api_key, to_atomic, token_mint, token_symbol};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── LIMIT ORDERS (JUPITER TRIGGER API) ──────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// "Buy SOL if it drops to $120". Jupiter keeps the order on-chain and fills
+// it when the price is reached; we only build the create and cancel
+// transactions for the user to sign.
+
+const TRIGGER_API: &str = "https://api.jup.ag/trigger/v1";
+
+/// Tokens a limit price is quoted in ("SOL at $120")
+const QUOTE_TOKENS: &[&str] = &["USDC", "USDT"];
+
+/// A built order, echoed in `meta`
+#[derive(Serialize, Debug)]
+pub struct OrderPlan {
+    pub order: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub making_amount: u64,
+    pub taking_amount: u64,
+    #[serde(skip)]
+    pub tx_base64: String,
+}
+
+/// One open order as returned by `getTriggerOrders`
+#[derive(Serialize, Debug)]
+pub struct OpenOrder {
+    pub order: String,
+    pub input: String,
+    pub output: String,
+    pub making_amount: f64,
+    pub taking_amount: f64,
+    /// Per unit of the non-stablecoin side, as given to `create_order`
+    pub limit_price: Option<f64>,
+    pub created_at: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+/// How much to ask for when paying `amount` of `token_in` at `limit_price`.
+/// The price is per unit of the non-stablecoin side, so "buy SOL at 120" and
+/// "sell SOL at 200" both read naturally. One side must be a quote token.
+fn taking_amount(token_in: &str, amount: f64, limit_price: f64) -> f64 {
+    if QUOTE_TOKENS.contains(&token_in) {
+        amount / limit_price
+    } else {
+        amount * limit_price
+    }
+}
+
+/// Create a trigger order paying `amount` of `token_in` for `token_out` at
+/// `limit_price`
+pub async fn create_order(
+    token_in: &str,
+    token_out: &str,
+    amount: f64,
+    limit_price: f64,
+    user: &str,
+) -> Result<OrderPlan, String> {
+    let input_mint = token_mint(token_in)
+        .ok_or_else(|| format!("Unknown input token: '{}'. Supported: SOL, USDC, USDT, BONK, JUP, RAY, WIF", token_in))?;
+    let output_mint = token_mint(token_out)
+        .ok_or_else(|| format!("Unknown output token: '{}'. Supported: SOL, USDC, USDT, BONK, JUP, RAY, WIF", token_out))?;
+    if input_mint == output_mint {
+        return Err("Input and output tokens must differ".to_string());
+    }
+    if !QUOTE_TOKENS.contains(&token_in) && !QUOTE_TOKENS.contains(&token_out) {
+        return Err("Limit prices are quoted in USDC or USDT, so one side of the order must be USDC or USDT".to_string());
+    }
+    if amount <= 0.0 {
+        return Err("Order amount must be greater than 0".to_string());
+    }
+    if !(limit_price > 0.0 && limit_price.is_finite()) {
+        return Err("What price should the order trigger at?".to_string());
+    }
+
+    let making_amount = to_atomic(token_in, amount);
+    let taking_amount = to_atomic(token_out, taking_amount(token_in, amount, limit_price));
+    if making_amount == 0 || taking_amount == 0 {
+        return Err("Order amount is too small".to_string());
+    }
+
+    let body = json!({
+        "inputMint": input_mint,
+        "outputMint": output_mint,
+        "maker": user,
+        "payer": user,
+        "params": {
+            "makingAmount": making_amount.to_string(),
+            "takingAmount": taking_amount.to_string(),
+        },
+        "computeUnitPrice": "auto",
+        "wrapAndUnwrapSol": true,
+    });
+    let res = post("createOrder", &body).await?;
+
+    Ok(OrderPlan {
+        order: res["order"].as_str().unwrap_or_default().to_string(),
+        input_mint: input_mint.to_string(),
+        output_mint: output_mint.to_string(),
+        making_amount,
+        taking_amount,
+        tx_base64: res["transaction"].as_str()
+            .ok_or("Jupiter response missing transaction field")?
+            .to_string(),
+    })
+}
+
+/// Cancel one order, or every open order when `order` is `None`. Returns
+/// the transactions to sign in order.
+pub async fn cancel_orders(user: &str, order: Option<&str>) -> Result<Vec<String>, String> {
+    match order {
+        Some(order) => {
+            let res = post("cancelOrder", &json!({ "maker": user, "order": order, "computeUnitPrice": "auto" })).await?;
+            let tx = res["transaction"].as_str()
+                .ok_or("Jupiter response missing transaction field")?;
+            Ok(vec![tx.to_string()])
+        },
+        None => {
+            let res = post("cancelOrders", &json!({ "maker": user, "computeUnitPrice": "auto" })).await?;
+            let txs: Vec<String> = res["transactions"].as_array()
+                .ok_or("Jupiter response missing transactions field")?
+                .iter()
+                .filter_map(|t| t.as_str().map(str::to_string))
+                .collect();
+            Ok(txs)
+        },
+    }
+}
+
+/// The user's open orders
+pub async fn list_orders(user: &str) -> Result<Vec<OpenOrder>, String> {
+    let api_key = jupiter_api_key()?;
+    let res = Client::new()
+        .get(format!("{}/getTriggerOrders", TRIGGER_API))
+        .query(&[("user", user), ("orderStatus", "active")])
+        .header("x-api-key", &api_key)
+        .send().await
+        .map_err(|e| format!("Jupiter trigger request failed: {}", e))?;
+    let data = parse(res, "getTriggerOrders").await?;
+
+    let orders = data["orders"].as_array().cloned().unwrap_or_default();
+    Ok(orders.iter().map(|o| {
+        let input_mint = o["inputMint"].as_str().unwrap_or_default();
+        let output_mint = o["outputMint"].as_str().unwrap_or_default();
+        let input = symbol_for(input_mint);
+        let output = symbol_for(output_mint);
+        let making_amount = ui_amount(&o["makingAmount"]);
+        let taking_amount = ui_amount(&o["takingAmount"]);
+        let limit_price = (making_amount > 0.0 && taking_amount > 0.0).then(|| {
+            if QUOTE_TOKENS.contains(&input.as_str()) { making_amount / taking_amount } else { taking_amount / making_amount }
+        });
+        OpenOrder {
+            order: o["orderKey"].as_str().unwrap_or_default().to_string(),
+            input,
+            output,
+            making_amount,
+            taking_amount,
+            limit_price,
+            created_at: o["createdAt"].as_str().map(str::to_string),
+            expires_at: o["expiredAt"].as_str().map(str::to_string),
+        }
+    }).collect())
+}
+
+/// Amounts come back as UI-unit strings
+fn ui_amount(v: &serde_json::Value) -> f64 {
+    v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_f64()).unwrap_or(0.0)
+}
+
+/// Registry symbol for a mint, or the mint itself
+fn symbol_for(mint: &str) -> String {
+    token_symbol(mint).unwrap_or(mint).to_string()
+}
+
+async fn post(endpoint: &str, body: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let api_key = jupiter_api_key()?;
+    let res = Client::new()
+        .post(format!("{}/{}", TRIGGER_API, endpoint))
+        .header("x-api-key", &api_key)
+        .json(body)
+        .send().await
+        .map_err(|e| format!("Jupiter trigger request failed: {}", e))?;
+    parse(res, endpoint).await
+}
+
+async fn parse(res: reqwest::Response, endpoint: &str) -> Result<serde_json::Value, String> {
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(format!("Jupiter {} error ({}): {}", endpoint, status, body));
+    }
+    let data: serde_json::Value = res.json().await
+        .map_err(|e| format!("Jupiter {} parse error: {}", endpoint, e))?;
+    if let Some(err) = data.get("error") {
+        return Err(format!("Jupiter {} error: {}", endpoint, err));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taking_amount_prices_the_non_stablecoin_side() {
+        // Buy SOL at $120 with 600 USDC
+        assert_eq!(taking_amount("USDC", 600.0, 120.0), 5.0);
+        // Sell 2 SOL at $200
+        assert_eq!(taking_amount("SOL", 2.0, 200.0), 400.0);
+    }
+
+    #[tokio::test]
+    async fn rejects_pairs_without_a_quote_token() {
+        let err = create_order("SOL", "BONK", 1.0, 100.0, "user").await.unwrap_err();
+        assert!(err.contains("USDC or USDT"), "{}", err);
+    }
+}
@@ -0,0 +1,207 @@
+use serde::Serialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// ═══════════════════════════════════════════════════════════════
+// ─── VALIDATOR DIRECTORY ─────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// Scores every voting validator so "stake with a good validator" has an
+// answer. Inputs are getVoteAccounts (commission, stake, vote credits),
+// getEpochInfo and getBlockProduction (skip rate), plus on-chain validator
+// info for names. The score is out of 100:
+//
+//   30 × (1 - commission) + 40 × credits vs. the best voter + 30 × (1 - skip rate)
+//   - VALIDATOR_CONCENTRATION_PENALTY points per 1% of total stake
+//
+// Delinquent validators are listed but score 0 and are never picked.
+
+const CONFIG_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Config1111111111111111111111111111111111111");
+const VALIDATOR_INFO_ID: Pubkey = solana_sdk::pubkey!("Va1idator1nfo111111111111111111111111111111");
+
+/// How long a directory snapshot is reused
+const DIRECTORY_TTL: Duration = Duration::from_secs(600);
+
+/// Default points deducted per 1% of cluster stake
+const DEFAULT_CONCENTRATION_PENALTY: f64 = 10.0;
+
+/// Words that mean "pick one for me"
+const ANY_VALIDATOR: &[&str] = &["", "best", "good", "top", "any", "a good validator", "the best validator", "recommended"];
+
+/// A directory snapshot and when it was taken
+type Snapshot = (Instant, Arc<Vec<ValidatorScore>>);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ValidatorScore {
+    pub vote_account: String,
+    pub identity: String,
+    pub name: Option<String>,
+    pub commission: u8,
+    pub activated_stake: u64,
+    /// Percent of all active stake
+    pub stake_share: f64,
+    /// Credits earned last epoch relative to the best voter (0..1)
+    pub credit_ratio: f64,
+    /// Skipped leader slots this epoch (0..1), if block production was available
+    pub skip_rate: Option<f64>,
+    /// Part of the smallest set of validators holding a third of the stake
+    pub superminority: bool,
+    pub delinquent: bool,
+    pub score: f64,
+}
+
+/// Scored validators per network, refreshed every `DIRECTORY_TTL`
+pub struct ValidatorDirectory {
+    cache: Mutex<HashMap<String, Snapshot>>,
+    concentration_penalty: f64,
+}
+
+impl ValidatorDirectory {
+    pub fn from_env() -> Self {
+        let concentration_penalty = env::var("VALIDATOR_CONCENTRATION_PENALTY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CONCENTRATION_PENALTY);
+        ValidatorDirectory { cache: Mutex::new(HashMap::new()), concentration_penalty }
+    }
+
+    /// All validators on `network`, best first
+    pub async fn list(&self, rpc: &RpcClient, network: &str) -> Result<Arc<Vec<ValidatorScore>>, String> {
+        if let Some((at, list)) = self.cache.lock().unwrap().get(network) {
+            if at.elapsed() < DIRECTORY_TTL {
+                return Ok(list.clone());
+            }
+        }
+
+        let list = Arc::new(build_directory(rpc, self.concentration_penalty).await?);
+        self.cache.lock().unwrap().insert(network.to_string(), (Instant::now(), list.clone()));
+        Ok(list)
+    }
+
+    /// Resolve what the user said - a vote account, identity, name, or
+    /// "a good validator" - to one validator
+    pub async fn resolve(&self, rpc: &RpcClient, network: &str, query: &str) -> Result<ValidatorScore, String> {
+        let list = self.list(rpc, network).await?;
+        let wanted = query.trim().to_lowercase();
+
+        if ANY_VALIDATOR.contains(&wanted.as_str()) {
+            return list.iter().find(|v| !v.delinquent).cloned()
+                .ok_or_else(|| "No healthy validators found".to_string());
+        }
+
+        if let Some(v) = list.iter().find(|v| v.vote_account == query.trim() || v.identity == query.trim()) {
+            return Ok(v.clone());
+        }
+
+        let named = |v: &&ValidatorScore| v.name.as_deref().is_some_and(|n| n.to_lowercase() == wanted);
+        let partial = |v: &&ValidatorScore| v.name.as_deref().is_some_and(|n| n.to_lowercase().contains(&wanted));
+        // The list is sorted by score, so the first match is the best-scoring one
+        list.iter().find(named).or_else(|| list.iter().find(partial)).cloned()
+            .ok_or_else(|| format!("No validator named '{}'. Use its vote account or ask for a good validator.", query.trim()))
+    }
+}
+
+async fn build_directory(rpc: &RpcClient, concentration_penalty: f64) -> Result<Vec<ValidatorScore>, String> {
+    let status = rpc.get_vote_accounts().await
+        .map_err(|e| format!("Failed to fetch vote accounts: {}", e))?;
+    let epoch = rpc.get_epoch_info().await
+        .map_err(|e| format!("Failed to fetch epoch info: {}", e))?
+        .epoch;
+    // Not every RPC serves block production; scoring falls back to credits alone
+    let production = rpc.get_block_production().await.ok().map(|r| r.value.by_identity);
+    let names = fetch_validator_names(rpc).await.unwrap_or_default();
+
+    let total_stake: u64 = status.current.iter().chain(&status.delinquent).map(|v| v.activated_stake).sum();
+    let last_epoch = epoch.saturating_sub(1);
+    let credits = |v: &solana_client::rpc_response::RpcVoteAccountInfo| {
+        v.epoch_credits.iter()
+            .find(|(e, _, _)| *e == last_epoch)
+            .map(|(_, credits, prev)| credits.saturating_sub(*prev))
+            .unwrap_or(0)
+    };
+    let best_credits = status.current.iter().map(credits).max().unwrap_or(0).max(1);
+
+    let mut by_stake: Vec<(&str, u64)> = status.current.iter().map(|v| (v.vote_pubkey.as_str(), v.activated_stake)).collect();
+    by_stake.sort_by_key(|v| std::cmp::Reverse(v.1));
+    let mut superminority = std::collections::HashSet::new();
+    let mut running = 0u64;
+    for (vote, stake) in by_stake {
+        if running * 3 >= total_stake {
+            break;
+        }
+        running += stake;
+        superminority.insert(vote.to_string());
+    }
+
+    let mut list: Vec<ValidatorScore> = status.current.iter().map(|v| (v, false))
+        .chain(status.delinquent.iter().map(|v| (v, true)))
+        .map(|(v, delinquent)| {
+            let stake_share = v.activated_stake as f64 * 100.0 / total_stake.max(1) as f64;
+            let credit_ratio = credits(v) as f64 / best_credits as f64;
+            let skip_rate = production.as_ref()
+                .and_then(|p| p.get(&v.node_pubkey))
+                .filter(|(leader_slots, _)| *leader_slots > 0)
+                .map(|(leader_slots, produced)| 1.0 - *produced as f64 / *leader_slots as f64);
+
+            let score = if delinquent {
+                0.0
+            } else {
+                let raw = 30.0 * (1.0 - v.commission as f64 / 100.0)
+                    + 40.0 * credit_ratio
+                    + 30.0 * (1.0 - skip_rate.unwrap_or(0.0))
+                    - concentration_penalty * stake_share;
+                raw.clamp(0.0, 100.0)
+            };
+
+            ValidatorScore {
+                vote_account: v.vote_pubkey.clone(),
+                identity: v.node_pubkey.clone(),
+                name: names.get(&v.node_pubkey).cloned(),
+                commission: v.commission,
+                activated_stake: v.activated_stake,
+                stake_share,
+                credit_ratio,
+                skip_rate,
+                superminority: superminority.contains(&v.vote_pubkey),
+                delinquent,
+                score,
+            }
+        })
+        .collect();
+
+    list.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(list)
+}
+
+/// Validator names published with `solana validator-info`, keyed by identity.
+/// Each Config account holds `[(VALIDATOR_INFO_ID, false), (identity, true)]`
+/// followed by a bincode string of JSON.
+async fn fetch_validator_names(rpc: &RpcClient) -> Result<HashMap<String, String>, String> {
+    let config = RpcProgramAccountsConfig {
+        // Offset 1 skips the compact-u16 key count
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(1, VALIDATOR_INFO_ID.as_ref()))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc.get_program_accounts_with_config(&CONFIG_PROGRAM_ID, config).await
+        .map_err(|e| format!("Failed to fetch validator info: {}", e))?;
+
+    Ok(accounts.into_iter().filter_map(|(_, account)| {
+        let data = account.data;
+        // 1 byte key count (2) + 2 × (32-byte key + signer flag)
+        let identity = Pubkey::try_from(data.get(34..66)?).ok()?;
+        let info: String = bincode::deserialize(data.get(67..)?).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&info).ok()?;
+        Some((identity.to_string(), json["name"].as_str()?.trim().to_string()))
+    }).collect())
+}
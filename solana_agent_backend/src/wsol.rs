@@ -0,0 +1,92 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::str::FromStr;
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::swap::token_mint;
+
+// ═══════════════════════════════════════════════════════════════
+// ─── WRAPPED SOL ─────────────────────────────────────────────
+// ═══════════════════════════════════════════════════════════════
+//
+// wSOL is an SPL token account whose balance is its lamports. Wrapping is
+// transfer + `sync_native`; unwrapping closes the account, which returns
+// every lamport (balance and rent) to the owner.
+
+/// The wrapped-SOL mint from the token registry
+pub fn wsol_mint() -> Pubkey {
+    Pubkey::from_str(token_mint("SOL").expect("SOL is in the token registry")).unwrap()
+}
+
+/// The user's wSOL associated token account
+pub fn wsol_account(user: &Pubkey) -> Pubkey {
+    get_associated_token_address(user, &wsol_mint())
+}
+
+/// Whether the user already has a wSOL account. Flows that wrap SOL only
+/// close the account afterwards when they created it, so wSOL the user
+/// already held isn't unwrapped along the way.
+pub async fn has_wsol_account(rpc: &RpcClient, user: &Pubkey) -> Result<bool, String> {
+    rpc.get_account_with_commitment(&wsol_account(user), rpc.commitment()).await
+        .map(|res| res.value.is_some())
+        .map_err(|e| format!("Failed to check wSOL account: {}", e))
+}
+
+/// Create the wSOL ATA if needed, move `lamports` in and sync its balance
+pub fn wrap_ixs(user: &Pubkey, lamports: u64) -> Result<Vec<Instruction>, String> {
+    let wsol = wsol_account(user);
+    Ok(vec![
+        create_associated_token_account_idempotent(user, user, &wsol_mint(), &spl_token::id()),
+        system_instruction::transfer(user, &wsol, lamports),
+        spl_token::instruction::sync_native(&spl_token::id(), &wsol)
+            .map_err(|e| format!("Failed to build sync_native ix: {}", e))?,
+    ])
+}
+
+/// Close the wSOL ATA back to native SOL
+pub fn unwrap_ix(user: &Pubkey) -> Result<Instruction, String> {
+    spl_token::instruction::close_account(&spl_token::id(), &wsol_account(user), user, user, &[])
+        .map_err(|e| format!("Failed to build close ix: {}", e))
+}
+
+/// Wrap `amount_sol` SOL
+pub fn build_wrap_tx(user: &str, amount_sol: f64) -> Result<String, String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+    if amount_sol <= 0.0 {
+        return Err("Amount to wrap must be greater than 0".to_string());
+    }
+    let lamports = (amount_sol * 1_000_000_000.0) as u64;
+    encode_tx(&wrap_ixs(&user_pub, lamports)?, &user_pub)
+}
+
+/// Unwrap the whole wSOL balance. Returns the tx and the wrapped amount in
+/// lamports; the account's rent comes back on top of that.
+pub async fn build_unwrap_tx(rpc: &RpcClient, user: &str) -> Result<(String, u64), String> {
+    let user_pub = Pubkey::from_str(user)
+        .map_err(|e| format!("Invalid user pubkey: {}", e))?;
+
+    let balance = rpc.get_token_account_balance(&wsol_account(&user_pub)).await
+        .map_err(|_| "You have no wrapped SOL account".to_string())?;
+    let lamports: u64 = balance.amount.parse()
+        .map_err(|e| format!("Invalid wSOL balance: {}", e))?;
+
+    Ok((encode_tx(&[unwrap_ix(&user_pub)?], &user_pub)?, lamports))
+}
+
+fn encode_tx(instructions: &[Instruction], payer: &Pubkey) -> Result<String, String> {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    Ok(general_purpose::STANDARD.encode(
+        bincode::serialize(&tx).map_err(|e| format!("Serialize error: {}", e))?
+    ))
+}
@@ -190,6 +190,7 @@ export default function App() {
   const [appState, setAppState] = useState("idle"); // idle | parsing | ready | confirming | sending | done | error
   const [interpretation, setInterpretation] = useState(null);
   const [pendingTx, setPendingTx] = useState(null);
+  const [queuedTxs, setQueuedTxs] = useState([]); // rest of a multi-transaction batch
   const [pendingAction, setPendingAction] = useState(null);
   const cardAnim = useRef(new Animated.Value(0)).current;
 
@@ -267,8 +268,16 @@ export default function App() {
           const sig = await connection.sendRawTransaction(signedTx);
           addLog(`[OK] Confirmed: ${sig.slice(0, 12)}...`);
           addLog(`[LINK] solscan.io/tx/${sig.slice(0, 16)}...${network === "devnet" ? " (devnet)" : ""}`);
-          setAppState("done");
           fetchBalance();
+          if (queuedTxs.length > 0) {
+            addLog(`[TX] ${queuedTxs.length} more transaction(s) to sign`);
+            setInterpretation({ action: "Next transaction", remaining: queuedTxs.length, network });
+            setPendingTx(queuedTxs[0]);
+            setQueuedTxs(queuedTxs.slice(1));
+            setAppState("ready");
+          } else {
+            setAppState("done");
+          }
         } catch (e) {
           addLog(`[ERROR] Send error: ${e.message}`);
           setAppState("error");
@@ -323,7 +332,8 @@ export default function App() {
   const refreshLogs = () => { setLogs([]); addLog("Logs cleared. Ready."); };
 
   // ─── SIGN TRANSACTION ───────────────────────────────────
-  const signAndSendTx = async (txBase64, partialSigners = []) => {
+  // keepBlockhash: the backend already set it (durable nonce or server-side partial signature)
+  const signAndSendTx = async (txBase64, partialSigners = [], keepBlockhash = false) => {
     if (!session || !sharedSecret || !phantomWalletPublicKey) {
       addLog("⚠️ Connect wallet first!");
       return;
@@ -340,14 +350,18 @@ export default function App() {
     try {
       const vtx = VersionedTransaction.deserialize(txBytes);
       addLog("[TX] Versioned transaction (v0)");
-      const { blockhash } = await connection.getLatestBlockhash();
-      vtx.message.recentBlockhash = blockhash;
+      if (!keepBlockhash) {
+        const { blockhash } = await connection.getLatestBlockhash();
+        vtx.message.recentBlockhash = blockhash;
+      }
       serializedTx = Buffer.from(vtx.serialize());
     } catch (_vErr) {
       try {
         const tx = Transaction.from(txBuffer);
-        const { blockhash } = await connection.getLatestBlockhash();
-        tx.recentBlockhash = blockhash;
+        if (!keepBlockhash) {
+          const { blockhash } = await connection.getLatestBlockhash();
+          tx.recentBlockhash = blockhash;
+        }
         tx.feePayer = phantomWalletPublicKey;
         if (partialSigners.length > 0) {
           tx.partialSign(...partialSigners);
@@ -398,7 +412,9 @@ export default function App() {
   };
 
   // ─── MAIN HANDLER: Parse Intent (V3 Two-Step Flow) ──────
-  const handleSend = async () => {
+  // confirmIrreversible is only true when re-sent from a CONFIRM_REQUIRED preview
+  // (onPress passes an event object, hence the strict check)
+  const handleSend = async (confirmIrreversible) => {
     if (!prompt.trim()) return;
     try {
       const currentPubkey = phantomWalletPublicKey
@@ -414,7 +430,12 @@ export default function App() {
       const res = await fetch(API_URL, {
         method: "POST",
         headers,
-        body: JSON.stringify({ prompt, user_pubkey: currentPubkey, network }),
+        body: JSON.stringify({
+          prompt,
+          user_pubkey: currentPubkey,
+          network,
+          confirm_irreversible: confirmIrreversible === true,
+        }),
       });
 
       if (res.status === 402) {
@@ -427,6 +448,15 @@ export default function App() {
 
       const data = await res.json();
 
+      // Irreversible actions come back as a preview first
+      if (data.action_type === "CONFIRM_REQUIRED") {
+        addLog(`[WARN] ${data.message}`);
+        setInterpretation(data.meta || { action: "Confirm", network });
+        setPendingAction(() => () => handleSend(true));
+        setAppState("ready");
+        return;
+      }
+
       if (data.action_type === "ERROR") {
         addLog(`[ERROR] ${data.message}`);
         setAppState("error");
@@ -435,7 +465,8 @@ export default function App() {
 
       addLog(`AI: ${data.message}`);
 
-      if (data.action_type === "MINT_NFT") {
+      // Older backends return no transaction for mints; fall back to building it here
+      if (data.action_type === "MINT_NFT" && !data.tx_base64) {
         const name = data.meta?.name || "AI Artwork";
         setInterpretation({ action: "Mint NFT", name, network, fee: "~0.01 SOL" });
         setPendingAction(() => () => mintNFT(name));
@@ -446,6 +477,7 @@ export default function App() {
       if (data.tx_base64) {
         setInterpretation(data.meta || { action: data.action_type, network });
         setPendingTx(data.tx_base64);
+        setQueuedTxs((data.meta?.transactions || []).slice(1));
         setAppState("ready");
         addLog("Review transaction below");
       }
@@ -459,15 +491,20 @@ export default function App() {
   const handleConfirm = async () => {
     setAppState("confirming");
     if (pendingAction) {
-      await pendingAction();
+      // Clear first: the action may queue up a new transaction to review
+      const action = pendingAction;
       setPendingAction(null);
+      setInterpretation(null);
+      setPendingTx(null);
+      await action();
+      return;
     } else if (pendingTx) {
       if (!phantomWalletPublicKey) {
         addLog("[WARN] Connect wallet first!");
         setAppState("error");
         return;
       }
-      await signAndSendTx(pendingTx);
+      await signAndSendTx(pendingTx, [], !!interpretation?.keep_blockhash);
     }
     setInterpretation(null);
     setPendingTx(null);
@@ -476,6 +513,7 @@ export default function App() {
   const handleCancel = () => {
     setInterpretation(null);
     setPendingTx(null);
+    setQueuedTxs([]);
     setPendingAction(null);
     setAppState("idle");
     addLog("Cancelled");
@@ -485,6 +523,7 @@ export default function App() {
     setAppState("idle");
     setInterpretation(null);
     setPendingTx(null);
+    setQueuedTxs([]);
     setPendingAction(null);
     setPrompt("");
   };